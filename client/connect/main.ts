@@ -0,0 +1,12 @@
+// Connect Another Device window in the desktop app (connect.html)
+
+import { mount } from 'svelte'
+import '../app.css'
+import '../styles/variables.css'
+import ConnectPage from './ConnectPage.svelte'
+
+const app = mount(ConnectPage, {
+  target: document.getElementById('app')!,
+})
+
+export default app
@@ -0,0 +1,85 @@
+// Typed access to the desktop app's commands and events (docs/PROTOCOL.md)
+// Only defined in the app's own windows, never in a browser
+
+import type { BridgeStatus } from '../../protocol';
+
+export interface AppStatus {
+  version: string;
+  port: number;
+  /** null if the bridge isn't answering */
+  bridge: BridgeStatus | null;
+  ableton: {
+    responding: boolean;
+    liveVersion: string | null;
+  };
+  /** URLs other devices can open, LAN addresses first */
+  urls: string[];
+  scriptInstalled: boolean;
+  restartRequired: boolean;
+  updateAvailable: string | null;
+}
+
+/** The Ableton Link session, which the app joins itself (no bridge needed) */
+export interface LinkStatus {
+  enabled: boolean;
+  /** Other Link apps in the session */
+  peers: number;
+  tempo: number;
+  /** Beats on the session's timeline when read */
+  beat: number;
+  /** Position in the bar, from 0 up to quantum */
+  phase: number;
+  quantum: number;
+  playing: boolean;
+}
+
+interface TauriGlobal {
+  core: {
+    invoke<T>(command: string, args?: Record<string, unknown>): Promise<T>;
+  };
+  event: {
+    listen<T>(event: string, handler: (event: { payload: T }) => void): Promise<() => void>;
+  };
+}
+
+function tauri(): TauriGlobal {
+  const global = (window as unknown as { __TAURI__?: TauriGlobal }).__TAURI__;
+  if (!global) {
+    throw new Error('Not running in the Mission Control app');
+  }
+  return global;
+}
+
+export function isDesktopApp(): boolean {
+  return '__TAURI__' in window;
+}
+
+export function getStatus(): Promise<AppStatus> {
+  return tauri().core.invoke('get_status');
+}
+
+export function getLinkStatus(): Promise<LinkStatus> {
+  return tauri().core.invoke('get_link_status');
+}
+
+export function getUrls(): Promise<string[]> {
+  return tauri().core.invoke('get_urls');
+}
+
+/** QR code for `text` as a base64 PNG */
+export function getQrCode(text: string): Promise<string> {
+  return tauri().core.invoke('get_qr_code', { text });
+}
+
+/** Subscribe to status changes. Resolves to an unsubscribe function. */
+export function onStatusChanged(handler: (status: AppStatus) => void): Promise<() => void> {
+  return tauri().event.listen<AppStatus>('status-changed', (event) => handler(event.payload));
+}
+
+/**
+ * Subscribe to Link being turned on or off, or its tempo, peers or playing
+ * changing. Not sent for every beat. Resolves to an unsubscribe function.
+ */
+export function onLinkChanged(handler: (status: LinkStatus) => void): Promise<() => void> {
+  return tauri().event.listen<LinkStatus>('link-changed', (event) => handler(event.payload));
+}
@@ -0,0 +1,242 @@
+//! Reconnecting WebSocket client for the bridge
+//!
+//! Behaves like client/lib/connection.ts plus the session handling in
+//! App.svelte: it requests the session on connect, applies patches to it, and
+//! reconnects after a delay whenever the connection drops.
+
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::types::{ClientMessage, ServerMessage, SessionState};
+
+/// Same as the web UI
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Server messages kept for slow subscribers before they start missing some
+const MESSAGE_BUFFER: usize = 256;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConnectionState {
+    #[default]
+    Disconnected,
+    Connecting,
+    Connected,
+}
+
+/// What the client knows about the bridge and Live
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub connection: ConnectionState,
+    pub ableton_connected: bool,
+    /// `None` until the first `session` message, and after `session_reset`.
+    /// Kept while reconnecting.
+    pub session: Option<SessionState>,
+}
+
+impl Snapshot {
+    /// The session, while connected to a bridge that's connected to Live. The
+    /// kept session is stale while reconnecting or while Live is away.
+    pub fn live_session(&self) -> Option<&SessionState> {
+        self.session
+            .as_ref()
+            .filter(|_| self.connection == ConnectionState::Connected && self.ableton_connected)
+    }
+}
+
+pub struct Client {
+    outgoing: mpsc::UnboundedSender<ClientMessage>,
+    snapshot: watch::Receiver<Snapshot>,
+    messages: broadcast::Sender<ServerMessage>,
+    task: JoinHandle<()>,
+}
+
+impl Client {
+    /// Connect to the bridge's WebSocket at `url`, e.g. from [`url_for_port`].
+    /// Must be called within a Tokio runtime. Stops when dropped.
+    pub fn connect(url: impl Into<String>) -> Client {
+        Client::connect_with_delay(url, RECONNECT_DELAY)
+    }
+
+    /// [`Client::connect`], waiting `reconnect_delay` between attempts
+    pub fn connect_with_delay(url: impl Into<String>, reconnect_delay: Duration) -> Client {
+        let (outgoing, requests) = mpsc::unbounded_channel();
+        let (snapshot_sender, snapshot) = watch::channel(Snapshot::default());
+        let (messages, _) = broadcast::channel(MESSAGE_BUFFER);
+
+        let task = tokio::spawn(run(
+            url.into(),
+            reconnect_delay,
+            requests,
+            snapshot_sender,
+            messages.clone(),
+        ));
+
+        Client {
+            outgoing,
+            snapshot,
+            messages,
+            task,
+        }
+    }
+
+    /// Send a message to the bridge. Like the web UI, messages sent while
+    /// disconnected are dropped rather than queued.
+    pub fn send(&self, message: ClientMessage) -> Result<(), String> {
+        if self.snapshot.borrow().connection != ConnectionState::Connected {
+            return Err("Not connected to the bridge".to_string());
+        }
+        self.outgoing
+            .send(message)
+            .map_err(|_| "Bridge client has stopped".to_string())
+    }
+
+    /// The current state. Clones the session, so prefer [`Client::watch`] for
+    /// frequent reads.
+    pub fn snapshot(&self) -> Snapshot {
+        self.snapshot.borrow().clone()
+    }
+
+    /// Watch the state, which changes on every applied message
+    pub fn watch(&self) -> watch::Receiver<Snapshot> {
+        self.snapshot.clone()
+    }
+
+    /// Receive every message from the bridge, after it has been applied
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerMessage> {
+        self.messages.subscribe()
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// The bridge's WebSocket URL on this machine
+pub fn url_for_port(port: u16) -> String {
+    format!("ws://localhost:{}/ws", port)
+}
+
+async fn run(
+    url: String,
+    reconnect_delay: Duration,
+    mut requests: mpsc::UnboundedReceiver<ClientMessage>,
+    snapshot: watch::Sender<Snapshot>,
+    messages: broadcast::Sender<ServerMessage>,
+) {
+    loop {
+        snapshot.send_modify(|s| s.connection = ConnectionState::Connecting);
+        match tokio_tungstenite::connect_async(url.as_str()).await {
+            Ok((socket, _)) => {
+                log::info!("[protocol] Connected to {}", url);
+                snapshot.send_modify(|s| s.connection = ConnectionState::Connected);
+                if let Err(e) = serve(socket, &mut requests, &snapshot, &messages).await {
+                    log::warn!("[protocol] {}", e);
+                }
+                log::info!("[protocol] Disconnected");
+            }
+            Err(e) => log::debug!("[protocol] Could not connect to {}: {}", url, e),
+        }
+
+        snapshot.send_modify(|s| {
+            s.connection = ConnectionState::Disconnected;
+            s.ableton_connected = false;
+        });
+        // Anything sent while connecting is stale by now
+        while requests.try_recv().is_ok() {}
+        tokio::time::sleep(reconnect_delay).await;
+    }
+}
+
+/// Exchange messages until the connection closes
+async fn serve<S>(
+    socket: tokio_tungstenite::WebSocketStream<S>,
+    requests: &mut mpsc::UnboundedReceiver<ClientMessage>,
+    snapshot: &watch::Sender<Snapshot>,
+    messages: &broadcast::Sender<ServerMessage>,
+) -> Result<(), String>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let (mut sink, mut stream) = socket.split();
+    let send = |message: &ClientMessage| {
+        serde_json::to_string(message)
+            .map(Message::text)
+            .map_err(|e| format!("Could not encode message: {}", e))
+    };
+
+    sink.send(send(&ClientMessage::SessionRequest)?)
+        .await
+        .map_err(|e| format!("Could not request session: {}", e))?;
+
+    loop {
+        tokio::select! {
+            request = requests.recv() => {
+                // The client was dropped
+                let Some(request) = request else { return Ok(()) };
+                sink.send(send(&request)?)
+                    .await
+                    .map_err(|e| format!("Could not send message: {}", e))?;
+            }
+            incoming = stream.next() => {
+                let text = match incoming {
+                    None => return Ok(()),
+                    Some(Err(e)) => return Err(format!("Connection error: {}", e)),
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) => return Ok(()),
+                    Some(Ok(_)) => continue,
+                };
+                let message: ServerMessage = match serde_json::from_str(&text) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        log::warn!("[protocol] Ignoring message: {}", e);
+                        continue;
+                    }
+                };
+
+                if apply(snapshot, &message) {
+                    log::debug!("[protocol] Structure changed, requesting new session");
+                    sink.send(send(&ClientMessage::SessionRequest)?)
+                        .await
+                        .map_err(|e| format!("Could not request session: {}", e))?;
+                }
+                // No subscribers is fine
+                let _ = messages.send(message);
+            }
+        }
+    }
+}
+
+/// Apply a message to the snapshot. Returns whether the session needs
+/// requesting again.
+fn apply(snapshot: &watch::Sender<Snapshot>, message: &ServerMessage) -> bool {
+    let mut stale = false;
+    snapshot.send_if_modified(|s| match message {
+        ServerMessage::Connected { ableton_connected } => {
+            s.ableton_connected = *ableton_connected;
+            true
+        }
+        ServerMessage::Session { payload } => {
+            s.session = Some(payload.clone());
+            true
+        }
+        ServerMessage::SessionReset => {
+            s.session = None;
+            true
+        }
+        ServerMessage::Patch { payload } => match &mut s.session {
+            Some(session) => {
+                stale = !session.apply(payload);
+                !stale
+            }
+            None => false,
+        },
+        ServerMessage::SyncPhase { .. } | ServerMessage::Error { .. } => false,
+    });
+    stale
+}
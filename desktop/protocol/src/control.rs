@@ -0,0 +1,193 @@
+//! OSC control: an address space for OSC controllers such as TouchOSC and Open
+//! Stage Control, translated into the bridge's client messages
+//!
+//! The addresses are listed under OSC Control in docs/MANUAL.md. Scenes,
+//! tracks and clips are numbered from 1 in the order Live shows them, or named.
+
+use std::io;
+
+use rosc::{OscMessage, OscPacket, OscType};
+use tokio::net::UdpSocket;
+
+use crate::types::{ClientMessage, SessionState};
+
+/// Every control address starts with this
+pub const PREFIX: &str = "/mc";
+
+/// Largest datagram to expect from OSC apps, with room to spare
+pub const MAX_DATAGRAM: usize = 65536;
+
+/// Tempos Live accepts
+const TEMPO_RANGE: std::ops::RangeInclusive<f64> = 20.0..=999.0;
+
+/// What `message` asks the bridge to do, given the session. Momentary actions
+/// ignore a lone 0 argument, so a button acts when pressed, not when released.
+pub fn translate(
+    message: &OscMessage,
+    session: Option<&SessionState>,
+) -> Result<Vec<ClientMessage>, String> {
+    let path: Vec<&str> = match message.addr.strip_prefix(PREFIX) {
+        Some(rest) if rest.starts_with('/') => rest[1..].split('/').collect(),
+        _ => return Err(format!("{} isn't a control address", message.addr)),
+    };
+    let args = &message.args;
+    if released(args) && !matches!(path[..], ["record" | "metronome" | "loop"] | [.., "volume"]) {
+        return Ok(Vec::new());
+    }
+    let session = || session.ok_or_else(|| "No session from Live yet".to_string());
+
+    let message = match path[..] {
+        ["play"] => ClientMessage::TransportPlay,
+        ["stop"] => ClientMessage::TransportStop,
+        ["play_stop"] if session()?.is_playing => ClientMessage::TransportStop,
+        ["play_stop"] => ClientMessage::TransportPlay,
+        ["record"] => ClientMessage::TransportRecord {
+            enabled: toggled(args, session()?.is_recording)?,
+        },
+        ["metronome"] => ClientMessage::TransportMetronome {
+            enabled: toggled(args, session()?.metronome)?,
+        },
+        ["loop"] => ClientMessage::TransportLoop {
+            enabled: toggled(args, session()?.r#loop)?,
+        },
+        ["tap_tempo"] => ClientMessage::TransportTapTempo,
+        ["tempo"] => ClientMessage::TransportTempo {
+            bpm: number(args.first())
+                .filter(|bpm| TEMPO_RANGE.contains(bpm))
+                .ok_or("Send the tempo in BPM, from 20 to 999")?,
+        },
+        ["stop_all"] => {
+            return Ok(session()?
+                .tracks
+                .iter()
+                .map(|track| ClientMessage::TrackStop { track_id: track.id })
+                .collect());
+        }
+        ["scene", "fire"] => ClientMessage::SceneFire {
+            scene_id: scene_id(session()?, args.first())?,
+        },
+        ["scene", scene, "fire"] => ClientMessage::SceneFire {
+            scene_id: scene_id(session()?, Some(&OscType::String(scene.to_string())))?,
+        },
+        ["clip", "fire"] => {
+            let session = session()?;
+            ClientMessage::ClipFire {
+                scene_id: scene_id(session, args.first())?,
+                track_id: track_id(session, args.get(1))?,
+            }
+        }
+        ["clip", scene, track, "fire"] => {
+            let session = session()?;
+            ClientMessage::ClipFire {
+                scene_id: scene_id(session, Some(&OscType::String(scene.to_string())))?,
+                track_id: track_id(session, Some(&OscType::String(track.to_string())))?,
+            }
+        }
+        ["track", "stop"] => ClientMessage::TrackStop {
+            track_id: track_id(session()?, args.first())?,
+        },
+        ["track", track, "stop"] => ClientMessage::TrackStop {
+            track_id: track_id(session()?, Some(&OscType::String(track.to_string())))?,
+        },
+        ["track", track, "volume"] => ClientMessage::MixerVolume {
+            track_id: track_id(session()?, Some(&OscType::String(track.to_string())))?,
+            value: number(args.first())
+                .filter(|value| (0.0..=1.0).contains(value))
+                .ok_or("Send the volume from 0 to 1")?,
+        },
+        _ => return Err(format!("{} isn't a control address", message.addr)),
+    };
+    Ok(vec![message])
+}
+
+/// A button being released: a single argument of 0 or false
+fn released(args: &[OscType]) -> bool {
+    match args {
+        [OscType::Bool(value)] => !value,
+        [arg] => number(Some(arg)) == Some(0.0),
+        _ => false,
+    }
+}
+
+/// On or off as sent, or the opposite of `current` without an argument
+fn toggled(args: &[OscType], current: bool) -> Result<bool, String> {
+    match args.first() {
+        None => Ok(!current),
+        Some(OscType::Bool(value)) => Ok(*value),
+        arg => number(arg)
+            .map(|value| value != 0.0)
+            .ok_or_else(|| "Send 1 for on, 0 for off, or nothing to toggle".to_string()),
+    }
+}
+
+fn number(arg: Option<&OscType>) -> Option<f64> {
+    match arg? {
+        OscType::Int(value) => Some((*value).into()),
+        OscType::Long(value) => Some(*value as f64),
+        OscType::Float(value) => Some((*value).into()),
+        OscType::Double(value) => Some(*value),
+        OscType::String(value) => value.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Index of the name `arg` numbers (from 1) or matches, ignoring case
+fn find(arg: Option<&OscType>, names: &[&str], what: &str) -> Result<usize, String> {
+    let (index, missing) = match (arg, number(arg)) {
+        (_, Some(number)) => (
+            Some(number)
+                .filter(|number| number.fract() == 0.0 && *number >= 1.0)
+                .map(|number| number as usize - 1)
+                .filter(|index| *index < names.len()),
+            format!("No {} {}", what, number),
+        ),
+        (Some(OscType::String(name)), None) => (
+            names
+                .iter()
+                .position(|candidate| candidate.trim().eq_ignore_ascii_case(name.trim())),
+            format!("No {} named {:?}", what, name),
+        ),
+        _ => return Err(format!("Send the {}'s number or name", what)),
+    };
+    index.ok_or(missing)
+}
+
+fn scene_id(session: &SessionState, arg: Option<&OscType>) -> Result<i32, String> {
+    let names: Vec<_> = session
+        .scenes
+        .iter()
+        .map(|scene| scene.name.as_str())
+        .collect();
+    Ok(session.scenes[find(arg, &names, "scene")?].id)
+}
+
+fn track_id(session: &SessionState, arg: Option<&OscType>) -> Result<i32, String> {
+    let names: Vec<_> = session
+        .tracks
+        .iter()
+        .map(|track| track.name.as_str())
+        .collect();
+    Ok(session.tracks[find(arg, &names, "track")?].id)
+}
+
+/// Receive control messages on `socket` and hand each to `handle`, including
+/// those in bundles, until receiving fails. Datagrams that aren't OSC are
+/// skipped.
+pub async fn serve(socket: UdpSocket, mut handle: impl FnMut(OscMessage)) -> io::Result<()> {
+    let mut buffer = vec![0; MAX_DATAGRAM];
+    loop {
+        let (length, from) = socket.recv_from(&mut buffer).await?;
+        match rosc::decoder::decode_udp(&buffer[..length]) {
+            Ok((_, packet)) => flatten(packet).into_iter().for_each(&mut handle),
+            Err(e) => log::debug!("[osc-control] Not an OSC packet from {}: {}", from, e),
+        }
+    }
+}
+
+/// The messages in a packet, including those in (nested) bundles
+pub fn flatten(packet: OscPacket) -> Vec<OscMessage> {
+    match packet {
+        OscPacket::Message(message) => vec![message],
+        OscPacket::Bundle(bundle) => bundle.content.into_iter().flat_map(flatten).collect(),
+    }
+}
@@ -0,0 +1,13 @@
+//! Types and a WebSocket client for the Mission Control bridge protocol
+//!
+//! The protocol is defined in protocol/types.ts and documented in
+//! docs/PROTOCOL.md. This crate lets Rust (the desktop app) talk to the bridge
+//! the same way the web UI does, and translates OSC from controllers into the
+//! same messages (see [`control`]).
+
+pub mod client;
+pub mod control;
+pub mod types;
+
+pub use client::{url_for_port, Client, ConnectionState, Snapshot};
+pub use types::*;
@@ -0,0 +1,378 @@
+//! Serde models mirroring protocol/types.ts
+//!
+//! Field names match the TypeScript (camelCase), so JSON round-trips unchanged.
+//! Keep the two in step when the protocol changes.
+
+use serde::{Deserialize, Serialize};
+
+// Session State Types
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionState {
+    pub tempo: f64,
+    pub is_playing: bool,
+    pub is_recording: bool,
+    pub punch_in: bool,
+    pub punch_out: bool,
+    pub metronome: bool,
+    pub r#loop: bool,
+    /// 0=none, 1=8 bars, ..., 13=1/32
+    pub clip_trigger_quantization: i32,
+    /// Current song time in beats
+    pub beat_time: f64,
+    pub tracks: Vec<Track>,
+    pub scenes: Vec<Scene>,
+    pub master_track: MasterTrack,
+    pub selected_track: i32,
+    pub selected_scene: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MasterTrack {
+    pub color: u32,
+    pub volume: f64,
+    pub pan: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Track {
+    pub id: i32,
+    pub name: String,
+    pub color: u32,
+    pub volume: f64,
+    pub pan: f64,
+    pub mute: bool,
+    pub solo: bool,
+    pub arm: bool,
+    /// -1 if no slot is playing
+    pub playing_slot_index: i32,
+    /// -1 if no slot is triggered
+    pub fired_slot_index: i32,
+    pub clips: Vec<ClipSlot>,
+    /// MIDI track
+    pub has_midi_input: bool,
+    /// Audio track
+    pub has_audio_input: bool,
+    /// Send levels (0-1), one per return track
+    pub sends: Vec<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scene {
+    pub id: i32,
+    pub name: String,
+    pub color: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipSlot {
+    pub track_index: i32,
+    pub scene_index: i32,
+    pub has_clip: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clip: Option<Clip>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Clip {
+    pub name: String,
+    pub color: u32,
+    pub is_playing: bool,
+    pub is_triggered: bool,
+    pub is_recording: bool,
+    pub playing_position: f64,
+    pub length: f64,
+    pub loop_start: f64,
+    pub loop_end: f64,
+    pub is_audio_clip: bool,
+    pub is_midi_clip: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Device {
+    pub id: i32,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub is_on: bool,
+    pub parameters: Vec<Parameter>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Parameter {
+    pub id: i32,
+    pub name: String,
+    pub value: f64,
+    pub min: f64,
+    pub max: f64,
+    pub is_quantized: bool,
+}
+
+// OSC Message Types
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum OscValue {
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+// WebSocket Protocol Types
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    #[serde(rename = "clip/fire", rename_all = "camelCase")]
+    ClipFire { track_id: i32, scene_id: i32 },
+    #[serde(rename = "clip/stop", rename_all = "camelCase")]
+    ClipStop { track_id: i32, scene_id: i32 },
+    #[serde(rename = "scene/fire", rename_all = "camelCase")]
+    SceneFire { scene_id: i32 },
+    #[serde(rename = "scene/create")]
+    SceneCreate {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<i32>,
+    },
+    #[serde(rename = "track/stop", rename_all = "camelCase")]
+    TrackStop { track_id: i32 },
+    #[serde(rename = "transport/play")]
+    TransportPlay,
+    #[serde(rename = "transport/stop")]
+    TransportStop,
+    #[serde(rename = "transport/record")]
+    TransportRecord { enabled: bool },
+    #[serde(rename = "transport/tempo")]
+    TransportTempo { bpm: f64 },
+    #[serde(rename = "transport/metronome")]
+    TransportMetronome { enabled: bool },
+    #[serde(rename = "transport/punchIn")]
+    TransportPunchIn { enabled: bool },
+    #[serde(rename = "transport/punchOut")]
+    TransportPunchOut { enabled: bool },
+    #[serde(rename = "transport/loop")]
+    TransportLoop { enabled: bool },
+    #[serde(rename = "transport/tapTempo")]
+    TransportTapTempo,
+    #[serde(rename = "transport/quantization")]
+    TransportQuantization { value: i32 },
+    #[serde(rename = "mixer/volume", rename_all = "camelCase")]
+    MixerVolume { track_id: i32, value: f64 },
+    #[serde(rename = "mixer/pan", rename_all = "camelCase")]
+    MixerPan { track_id: i32, value: f64 },
+    #[serde(rename = "mixer/masterVolume")]
+    MixerMasterVolume { value: f64 },
+    #[serde(rename = "mixer/masterPan")]
+    MixerMasterPan { value: f64 },
+    #[serde(rename = "mixer/send", rename_all = "camelCase")]
+    MixerSend {
+        track_id: i32,
+        send_index: i32,
+        value: f64,
+    },
+    #[serde(rename = "mixer/mute", rename_all = "camelCase")]
+    MixerMute { track_id: i32, muted: bool },
+    #[serde(rename = "mixer/solo", rename_all = "camelCase")]
+    MixerSolo { track_id: i32, soloed: bool },
+    #[serde(rename = "mixer/arm", rename_all = "camelCase")]
+    MixerArm { track_id: i32, armed: bool },
+    #[serde(rename = "device/parameter", rename_all = "camelCase")]
+    DeviceParameter {
+        track_id: i32,
+        device_id: i32,
+        parameter_id: i32,
+        value: f64,
+    },
+    #[serde(rename = "session/request")]
+    SessionRequest,
+    #[serde(rename = "session/resync")]
+    SessionResync,
+    #[serde(rename = "clip/move", rename_all = "camelCase")]
+    ClipMove {
+        src_track: i32,
+        src_scene: i32,
+        dst_track: i32,
+        dst_scene: i32,
+    },
+    #[serde(rename = "clip/delete", rename_all = "camelCase")]
+    ClipDelete { track_id: i32, scene_id: i32 },
+    #[serde(rename = "osc")]
+    Osc {
+        address: String,
+        args: Vec<OscValue>,
+    },
+}
+
+/// Patch payloads for granular state updates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind")]
+pub enum PatchPayload {
+    #[serde(rename = "transport", rename_all = "camelCase")]
+    Transport {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tempo: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        is_playing: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        is_recording: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        punch_in: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        punch_out: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        metronome: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        r#loop: Option<bool>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        clip_trigger_quantization: Option<i32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        beat_time: Option<f64>,
+    },
+    #[serde(rename = "track", rename_all = "camelCase")]
+    Track { track_index: i32, track: Track },
+    #[serde(rename = "clip", rename_all = "camelCase")]
+    Clip {
+        track_index: i32,
+        scene_index: i32,
+        clip_slot: ClipSlot,
+    },
+    #[serde(rename = "scene", rename_all = "camelCase")]
+    Scene { scene_index: i32, scene: Scene },
+    #[serde(rename = "masterTrack", rename_all = "camelCase")]
+    MasterTrack { master_track: MasterTrack },
+    #[serde(rename = "selection", rename_all = "camelCase")]
+    Selection {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        selected_track: Option<i32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        selected_scene: Option<i32>,
+    },
+    /// Tracks or scenes were added or removed: request the session again
+    #[serde(rename = "structure", rename_all = "camelCase")]
+    Structure { num_tracks: i32, num_scenes: i32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    #[serde(rename = "session")]
+    Session { payload: SessionState },
+    /// A new Live Set is loading; the session is gone until the next `session`
+    #[serde(rename = "session_reset")]
+    SessionReset,
+    #[serde(rename = "patch")]
+    Patch { payload: PatchPayload },
+    #[serde(rename = "connected", rename_all = "camelCase")]
+    Connected { ableton_connected: bool },
+    #[serde(rename = "sync_phase")]
+    SyncPhase {
+        phase: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        progress: Option<f64>,
+    },
+    #[serde(rename = "error")]
+    Error { message: String },
+}
+
+/// Bridge status served at `GET /api/status` (used by the desktop app)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeStatus {
+    /// Missing from older bridges
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub ableton_connected: bool,
+    pub is_playing: bool,
+    /// Connected WebSocket clients
+    pub clients: u32,
+    /// Clients connected from other machines
+    pub remote_clients: u32,
+}
+
+impl SessionState {
+    /// Apply a patch the way the web UI does. Returns `false` for a `structure`
+    /// patch, after which the session is stale and must be requested again.
+    pub fn apply(&mut self, patch: &PatchPayload) -> bool {
+        match patch {
+            PatchPayload::Transport {
+                tempo,
+                is_playing,
+                is_recording,
+                punch_in,
+                punch_out,
+                metronome,
+                r#loop,
+                clip_trigger_quantization,
+                beat_time,
+            } => {
+                set(&mut self.tempo, tempo);
+                set(&mut self.is_playing, is_playing);
+                set(&mut self.is_recording, is_recording);
+                set(&mut self.punch_in, punch_in);
+                set(&mut self.punch_out, punch_out);
+                set(&mut self.metronome, metronome);
+                set(&mut self.r#loop, r#loop);
+                set(
+                    &mut self.clip_trigger_quantization,
+                    clip_trigger_quantization,
+                );
+                set(&mut self.beat_time, beat_time);
+            }
+            PatchPayload::Track { track_index, track } => {
+                put(&mut self.tracks, *track_index, track.clone());
+            }
+            PatchPayload::Clip {
+                track_index,
+                scene_index,
+                clip_slot,
+            } => {
+                if let Some(track) = index(*track_index).and_then(|i| self.tracks.get_mut(i)) {
+                    put(&mut track.clips, *scene_index, clip_slot.clone());
+                }
+            }
+            PatchPayload::Scene { scene_index, scene } => {
+                put(&mut self.scenes, *scene_index, scene.clone());
+            }
+            PatchPayload::MasterTrack { master_track } => {
+                self.master_track = master_track.clone();
+            }
+            PatchPayload::Selection {
+                selected_track,
+                selected_scene,
+            } => {
+                set(&mut self.selected_track, selected_track);
+                set(&mut self.selected_scene, selected_scene);
+            }
+            PatchPayload::Structure { .. } => return false,
+        }
+        true
+    }
+}
+
+fn set<T: Clone>(field: &mut T, value: &Option<T>) {
+    if let Some(value) = value {
+        *field = value.clone();
+    }
+}
+
+fn index(i: i32) -> Option<usize> {
+    usize::try_from(i).ok()
+}
+
+/// Replace `items[i]`, or append if `i` is one past the end. Anything further
+/// out waits for the `structure` patch that follows.
+fn put<T>(items: &mut Vec<T>, i: i32, item: T) {
+    match index(i) {
+        Some(i) if i < items.len() => items[i] = item,
+        Some(i) if i == items.len() => items.push(item),
+        _ => {}
+    }
+}
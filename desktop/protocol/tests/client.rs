@@ -0,0 +1,199 @@
+//! The client against a mock bridge: a WebSocket server scripted per test
+
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use mission_control_protocol::{Client, ClientMessage, ConnectionState, ServerMessage, Snapshot};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+const SESSION: &str = include_str!("fixtures/session.json");
+const WAIT: Duration = Duration::from_secs(5);
+
+struct MockBridge {
+    listener: TcpListener,
+}
+
+impl MockBridge {
+    async fn start() -> MockBridge {
+        MockBridge {
+            listener: TcpListener::bind("127.0.0.1:0").await.unwrap(),
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("ws://{}/ws", self.listener.local_addr().unwrap())
+    }
+
+    /// Accept the next client, which must start by requesting the session
+    async fn accept(&self) -> Connection {
+        let (stream, _) = timeout(WAIT, self.listener.accept())
+            .await
+            .unwrap()
+            .unwrap();
+        let mut connection = Connection {
+            socket: tokio_tungstenite::accept_async(stream).await.unwrap(),
+        };
+        assert_eq!(connection.receive().await, ClientMessage::SessionRequest);
+        connection
+    }
+}
+
+struct Connection {
+    socket: WebSocketStream<TcpStream>,
+}
+
+impl Connection {
+    async fn send_json(&mut self, json: &str) {
+        self.socket.send(Message::text(json)).await.unwrap();
+    }
+
+    async fn send(&mut self, message: &ServerMessage) {
+        self.send_json(&serde_json::to_string(message).unwrap())
+            .await;
+    }
+
+    async fn receive(&mut self) -> ClientMessage {
+        loop {
+            match timeout(WAIT, self.socket.next())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap()
+            {
+                Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+                _ => continue,
+            }
+        }
+    }
+}
+
+/// Wait until the client's state satisfies `done`
+async fn wait_for(client: &Client, done: impl Fn(&Snapshot) -> bool) -> Snapshot {
+    let mut watch = client.watch();
+    let snapshot = timeout(WAIT, watch.wait_for(|snapshot| done(snapshot)))
+        .await
+        .expect("timed out waiting for client state")
+        .unwrap()
+        .clone();
+    snapshot
+}
+
+#[tokio::test]
+async fn applies_session_patches_and_reset() {
+    let bridge = MockBridge::start().await;
+    let client = Client::connect(bridge.url());
+    let mut connection = bridge.accept().await;
+
+    connection
+        .send(&ServerMessage::Connected {
+            ableton_connected: true,
+        })
+        .await;
+    connection.send_json(SESSION).await;
+    let snapshot = wait_for(&client, |s| s.session.is_some()).await;
+    assert_eq!(snapshot.connection, ConnectionState::Connected);
+    assert!(snapshot.ableton_connected);
+    assert_eq!(snapshot.session.unwrap().tracks.len(), 2);
+
+    connection
+        .send_json(r#"{ "type": "patch", "payload": { "kind": "transport", "tempo": 99 } }"#)
+        .await;
+    wait_for(&client, |s| {
+        s.session
+            .as_ref()
+            .is_some_and(|session| session.tempo == 99.0)
+    })
+    .await;
+
+    // Garbage is skipped, not fatal
+    connection
+        .send_json(r#"{ "type": "from-the-future" }"#)
+        .await;
+    connection.send(&ServerMessage::SessionReset).await;
+    wait_for(&client, |s| s.session.is_none()).await;
+    assert_eq!(client.snapshot().connection, ConnectionState::Connected);
+}
+
+#[tokio::test]
+async fn structure_patch_requests_the_session_again() {
+    let bridge = MockBridge::start().await;
+    let client = Client::connect(bridge.url());
+    let mut connection = bridge.accept().await;
+
+    connection.send_json(SESSION).await;
+    wait_for(&client, |s| s.session.is_some()).await;
+
+    connection
+        .send_json(r#"{ "type": "patch", "payload": { "kind": "structure", "numTracks": 3, "numScenes": 2 } }"#)
+        .await;
+    assert_eq!(connection.receive().await, ClientMessage::SessionRequest);
+}
+
+#[tokio::test]
+async fn sends_messages_and_forwards_what_it_receives() {
+    let bridge = MockBridge::start().await;
+    let client = Client::connect(bridge.url());
+    let mut messages = client.subscribe();
+
+    assert!(
+        client.send(ClientMessage::TransportPlay).is_err(),
+        "nothing to send to yet"
+    );
+
+    let mut connection = bridge.accept().await;
+    wait_for(&client, |s| s.connection == ConnectionState::Connected).await;
+    client
+        .send(ClientMessage::SceneFire { scene_id: 1 })
+        .unwrap();
+    assert_eq!(
+        connection.receive().await,
+        ClientMessage::SceneFire { scene_id: 1 }
+    );
+
+    let error = ServerMessage::Error {
+        message: "nope".to_string(),
+    };
+    connection.send(&error).await;
+    assert_eq!(
+        timeout(WAIT, messages.recv()).await.unwrap().unwrap(),
+        error
+    );
+}
+
+#[tokio::test]
+async fn reconnects_and_keeps_the_session_meanwhile() {
+    let bridge = MockBridge::start().await;
+    let client = Client::connect_with_delay(bridge.url(), Duration::from_millis(50));
+
+    let mut connection = bridge.accept().await;
+    connection
+        .send(&ServerMessage::Connected {
+            ableton_connected: true,
+        })
+        .await;
+    connection.send_json(SESSION).await;
+    let snapshot = wait_for(&client, |s| s.session.is_some()).await;
+    assert!(snapshot.live_session().is_some());
+
+    drop(connection);
+    let snapshot = wait_for(&client, |s| s.connection != ConnectionState::Connected).await;
+    assert!(!snapshot.ableton_connected);
+    assert!(snapshot.session.is_some());
+    // Kept, but not shown as Live's
+    assert!(snapshot.live_session().is_none());
+
+    // Requests the session again on reconnecting
+    let mut connection = bridge.accept().await;
+    connection
+        .send_json(&SESSION.replace("\"tempo\": 120", "\"tempo\": 140"))
+        .await;
+    wait_for(&client, |s| {
+        s.session
+            .as_ref()
+            .is_some_and(|session| session.tempo == 140.0)
+    })
+    .await;
+}
@@ -0,0 +1,300 @@
+//! OSC control: translating addresses, and serving them to a mock bridge from a
+//! local UDP client
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use mission_control_protocol::control::{self, translate};
+use mission_control_protocol::{
+    Client, ClientMessage, ConnectionState, ServerMessage, SessionState,
+};
+use rosc::{OscBundle, OscMessage, OscPacket, OscTime, OscType};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+const SESSION: &str = include_str!("fixtures/session.json");
+const WAIT: Duration = Duration::from_secs(5);
+
+fn session() -> SessionState {
+    match serde_json::from_str(SESSION).unwrap() {
+        ServerMessage::Session { payload } => payload,
+        other => panic!("not a session: {:?}", other),
+    }
+}
+
+fn message(addr: &str, args: Vec<OscType>) -> OscMessage {
+    OscMessage {
+        addr: addr.to_string(),
+        args,
+    }
+}
+
+fn translated(addr: &str, args: Vec<OscType>) -> Result<Vec<ClientMessage>, String> {
+    translate(&message(addr, args), Some(&session()))
+}
+
+#[test]
+fn fires_scenes_and_clips_by_number_or_name() {
+    let verse = Ok(vec![ClientMessage::SceneFire { scene_id: 1 }]);
+    assert_eq!(translated("/mc/scene/fire", vec![OscType::Int(2)]), verse);
+    assert_eq!(
+        translated("/mc/scene/fire", vec![OscType::String(" verse ".into())]),
+        verse
+    );
+    assert_eq!(translated("/mc/scene/2/fire", vec![]), verse);
+    assert_eq!(
+        translated("/mc/scene/Verse/fire", vec![OscType::Float(1.0)]),
+        verse
+    );
+
+    let vocals_intro = Ok(vec![ClientMessage::ClipFire {
+        track_id: 1,
+        scene_id: 0,
+    }]);
+    assert_eq!(
+        translated("/mc/clip/fire", vec![OscType::Int(1), OscType::Int(2)]),
+        vocals_intro
+    );
+    assert_eq!(translated("/mc/clip/1/2/fire", vec![]), vocals_intro);
+    assert_eq!(
+        translated(
+            "/mc/clip/fire",
+            vec![
+                OscType::String("intro".into()),
+                OscType::String("Vocals".into())
+            ]
+        ),
+        vocals_intro
+    );
+
+    assert_eq!(
+        translated("/mc/scene/fire", vec![OscType::String("Chorus".into())]),
+        Err("No scene named \"Chorus\"".to_string())
+    );
+    assert_eq!(
+        translated("/mc/clip/3/1/fire", vec![]),
+        Err("No scene 3".to_string())
+    );
+    assert_eq!(
+        translated("/mc/scene/fire", vec![]),
+        Err("Send the scene's number or name".to_string())
+    );
+}
+
+#[test]
+fn buttons_act_when_pressed() {
+    assert_eq!(
+        translated("/mc/play", vec![OscType::Float(1.0)]),
+        Ok(vec![ClientMessage::TransportPlay])
+    );
+    assert_eq!(
+        translated("/mc/play", vec![OscType::Float(0.0)]),
+        Ok(vec![])
+    );
+    assert_eq!(
+        translated("/mc/scene/1/fire", vec![OscType::Bool(false)]),
+        Ok(vec![])
+    );
+    // The session fixture is playing
+    assert_eq!(
+        translated("/mc/play_stop", vec![]),
+        Ok(vec![ClientMessage::TransportStop])
+    );
+    assert_eq!(
+        translated("/mc/stop_all", vec![OscType::Int(1)]),
+        Ok(vec![
+            ClientMessage::TrackStop { track_id: 0 },
+            ClientMessage::TrackStop { track_id: 1 },
+        ])
+    );
+}
+
+#[test]
+fn toggles_follow_the_argument_or_flip() {
+    // The session fixture's metronome is on
+    assert_eq!(
+        translated("/mc/metronome", vec![]),
+        Ok(vec![ClientMessage::TransportMetronome { enabled: false }])
+    );
+    assert_eq!(
+        translated("/mc/metronome", vec![OscType::Float(1.0)]),
+        Ok(vec![ClientMessage::TransportMetronome { enabled: true }])
+    );
+    assert_eq!(
+        translated("/mc/record", vec![OscType::Int(0)]),
+        Ok(vec![ClientMessage::TransportRecord { enabled: false }])
+    );
+    assert_eq!(
+        translated("/mc/track/Drums/volume", vec![OscType::Float(0.0)]),
+        Ok(vec![ClientMessage::MixerVolume {
+            track_id: 0,
+            value: 0.0
+        }])
+    );
+    assert_eq!(
+        translated("/mc/track/2/volume", vec![OscType::Float(1.5)]),
+        Err("Send the volume from 0 to 1".to_string())
+    );
+}
+
+#[test]
+fn keeps_the_tempo_in_range() {
+    for bpm in [20.0, 999.0] {
+        assert_eq!(
+            translated("/mc/tempo", vec![OscType::Float(bpm)]),
+            Ok(vec![ClientMessage::TransportTempo { bpm: bpm as f64 }])
+        );
+    }
+    for bpm in [19.9, 1000.0, f32::NAN, f32::INFINITY] {
+        assert_eq!(
+            translated("/mc/tempo", vec![OscType::Float(bpm)]),
+            Err("Send the tempo in BPM, from 20 to 999".to_string())
+        );
+    }
+}
+
+#[test]
+fn flattens_nested_bundles() {
+    let bundle = |content| {
+        OscPacket::Bundle(OscBundle {
+            timetag: OscTime::from((0, 1)),
+            content,
+        })
+    };
+    let packet = bundle(vec![
+        OscPacket::Message(message("/mc/play", vec![])),
+        bundle(vec![OscPacket::Message(message("/mc/stop", vec![]))]),
+    ]);
+    assert_eq!(
+        control::flatten(packet),
+        vec![message("/mc/play", vec![]), message("/mc/stop", vec![])]
+    );
+}
+
+#[test]
+fn needs_a_session_only_to_look_things_up() {
+    assert_eq!(
+        translate(&message("/mc/stop", vec![]), None),
+        Ok(vec![ClientMessage::TransportStop])
+    );
+    assert_eq!(
+        translate(&message("/mc/scene/1/fire", vec![]), None),
+        Err("No session from Live yet".to_string())
+    );
+    assert_eq!(
+        translate(&message("/live/song/start_playing", vec![]), None),
+        Err("/live/song/start_playing isn't a control address".to_string())
+    );
+}
+
+async fn receive(bridge: &mut WebSocketStream<TcpStream>) -> ClientMessage {
+    loop {
+        match timeout(WAIT, bridge.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap()
+        {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            _ => continue,
+        }
+    }
+}
+
+/// A controller's datagrams reach a mock bridge as client messages, through a
+/// real client
+#[tokio::test]
+async fn serves_a_local_udp_client() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = Arc::new(Client::connect(format!(
+        "ws://{}/ws",
+        listener.local_addr().unwrap()
+    )));
+    let (stream, _) = timeout(WAIT, listener.accept()).await.unwrap().unwrap();
+    let mut bridge = tokio_tungstenite::accept_async(stream).await.unwrap();
+    assert_eq!(receive(&mut bridge).await, ClientMessage::SessionRequest);
+    bridge.send(Message::text(SESSION)).await.unwrap();
+    timeout(
+        WAIT,
+        client.watch().wait_for(|snapshot| {
+            snapshot.connection == ConnectionState::Connected && snapshot.session.is_some()
+        }),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let address = server.local_addr().unwrap();
+    let (errors, mut error) = mpsc::unbounded_channel();
+    let sender = client.clone();
+    tokio::spawn(control::serve(server, move |message| {
+        match translate(&message, sender.snapshot().session.as_ref()) {
+            Ok(messages) => messages
+                .into_iter()
+                .for_each(|message| sender.send(message).unwrap()),
+            Err(e) => errors.send(e).unwrap(),
+        }
+    }));
+
+    let controller = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let send = |packet: OscPacket| {
+        let controller = &controller;
+        async move {
+            let bytes = rosc::encoder::encode(&packet).unwrap();
+            controller.send_to(&bytes, address).await.unwrap();
+        }
+    };
+
+    // A button press and release, then a bundle
+    send(OscPacket::Message(message(
+        "/mc/clip/2/1/fire",
+        vec![OscType::Float(1.0)],
+    )))
+    .await;
+    send(OscPacket::Message(message(
+        "/mc/clip/2/1/fire",
+        vec![OscType::Float(0.0)],
+    )))
+    .await;
+    send(OscPacket::Bundle(OscBundle {
+        timetag: OscTime::from((0, 1)),
+        content: vec![
+            OscPacket::Message(message(
+                "/mc/scene/fire",
+                vec![OscType::String("Intro".into())],
+            )),
+            OscPacket::Message(message("/mc/tempo", vec![OscType::Int(128)])),
+        ],
+    }))
+    .await;
+    send(OscPacket::Message(message(
+        "/mc/scene/fire",
+        vec![OscType::String("Outro".into())],
+    )))
+    .await;
+
+    assert_eq!(
+        receive(&mut bridge).await,
+        ClientMessage::ClipFire {
+            track_id: 0,
+            scene_id: 1
+        }
+    );
+    assert_eq!(
+        receive(&mut bridge).await,
+        ClientMessage::SceneFire { scene_id: 0 }
+    );
+    assert_eq!(
+        receive(&mut bridge).await,
+        ClientMessage::TransportTempo { bpm: 128.0 }
+    );
+    assert_eq!(
+        timeout(WAIT, error.recv()).await.unwrap().unwrap(),
+        "No scene named \"Outro\""
+    );
+}
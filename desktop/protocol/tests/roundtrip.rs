@@ -0,0 +1,142 @@
+//! Every fixture survives JSON -> Rust -> JSON unchanged, and patches apply
+//! the way the web UI applies them
+
+use mission_control_protocol::{ClientMessage, PatchPayload, ServerMessage};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+const CLIENT_MESSAGES: &str = include_str!("fixtures/client_messages.json");
+const SERVER_MESSAGES: &str = include_str!("fixtures/server_messages.json");
+const SESSION: &str = include_str!("fixtures/session.json");
+
+/// Compare JSON the way JavaScript would: `120` and `120.0` are the same number
+fn same_json(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64() == b.as_f64(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_json(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| same_json(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+fn assert_round_trips<T: Serialize + DeserializeOwned>(fixture: &Value) {
+    let parsed: T = serde_json::from_value(fixture.clone())
+        .unwrap_or_else(|e| panic!("could not parse {}: {}", fixture, e));
+    let serialized = serde_json::to_value(&parsed).unwrap();
+    assert!(
+        same_json(fixture, &serialized),
+        "{} became {}",
+        fixture,
+        serialized
+    );
+}
+
+fn fixtures(json: &str) -> Vec<Value> {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn client_messages_round_trip() {
+    for fixture in fixtures(CLIENT_MESSAGES) {
+        assert_round_trips::<ClientMessage>(&fixture);
+    }
+}
+
+#[test]
+fn server_messages_round_trip() {
+    for fixture in fixtures(SERVER_MESSAGES) {
+        assert_round_trips::<ServerMessage>(&fixture);
+    }
+    assert_round_trips::<ServerMessage>(&serde_json::from_str(SESSION).unwrap());
+}
+
+#[test]
+fn unknown_message_types_are_rejected() {
+    assert!(serde_json::from_str::<ServerMessage>(r#"{ "type": "bogus" }"#).is_err());
+    assert!(serde_json::from_str::<ClientMessage>(r#"{ "type": "clip/fire" }"#).is_err());
+}
+
+#[test]
+fn patches_apply_like_the_web_ui() {
+    let ServerMessage::Session {
+        payload: mut session,
+    } = serde_json::from_str(SESSION).unwrap()
+    else {
+        panic!("session fixture is not a session");
+    };
+
+    let patches: Vec<PatchPayload> = fixtures(SERVER_MESSAGES)
+        .into_iter()
+        .filter_map(|message| match serde_json::from_value(message).unwrap() {
+            ServerMessage::Patch { payload } => Some(payload),
+            _ => None,
+        })
+        .collect();
+
+    let (structure, patches) = patches.split_last().unwrap();
+    for patch in patches {
+        assert!(session.apply(patch), "{:?} should apply", patch);
+    }
+
+    // Transport fields only change when present
+    assert_eq!(session.tempo, 128.0);
+    assert!(!session.is_playing);
+    assert!(session.metronome);
+    assert!(session.r#loop);
+    assert_eq!(session.beat_time, 32.5);
+    assert_eq!(session.clip_trigger_quantization, 7);
+
+    // Tracks, clips and scenes are replaced whole
+    assert_eq!(session.tracks[1].name, "Lead Vocals");
+    assert!(session.tracks[1].mute);
+    assert!(!session.tracks[1].clips[1].has_clip);
+    let fill = session.tracks[0].clips[1].clip.as_ref().unwrap();
+    assert_eq!(fill.name, "Fill");
+    assert!(fill.is_triggered);
+    assert_eq!(session.scenes[1].name, "Chorus");
+    assert_eq!(session.master_track.volume, 0.5);
+
+    assert_eq!(session.selected_track, 0);
+    assert_eq!(session.selected_scene, 1);
+
+    // A structure change means the session must be requested again
+    let before = session.clone();
+    assert!(!session.apply(structure));
+    assert_eq!(session, before);
+}
+
+#[test]
+fn patches_beyond_the_session_are_ignored() {
+    let ServerMessage::Session {
+        payload: mut session,
+    } = serde_json::from_str(SESSION).unwrap()
+    else {
+        panic!("session fixture is not a session");
+    };
+    let before = session.clone();
+
+    let patch: PatchPayload = serde_json::from_value(serde_json::json!({
+        "kind": "scene",
+        "sceneIndex": 7,
+        "scene": { "id": 7, "name": "Outro", "color": 0 }
+    }))
+    .unwrap();
+    assert!(session.apply(&patch));
+    assert_eq!(session, before);
+
+    let patch: PatchPayload = serde_json::from_value(serde_json::json!({
+        "kind": "clip",
+        "trackIndex": 9,
+        "sceneIndex": 0,
+        "clipSlot": { "trackIndex": 9, "sceneIndex": 0, "hasClip": false }
+    }))
+    .unwrap();
+    assert!(session.apply(&patch));
+    assert_eq!(session, before);
+}
@@ -1,3 +1,16 @@
 fn main() {
-    tauri_build::build()
+    // Generates an `allow-<command>` permission for each of our commands, for
+    // capabilities/default.json to grant
+    tauri_build::try_build(tauri_build::Attributes::new().app_manifest(
+        tauri_build::AppManifest::new().commands(&[
+            "get_status",
+            "get_link_status",
+            "get_urls",
+            "get_qr_code",
+            "install_script",
+            "restart_bridge",
+            "check_updates",
+        ]),
+    ))
+    .expect("failed to run tauri-build");
 }
@@ -0,0 +1,120 @@
+//! Ableton Live process detection
+
+use std::path::Path;
+use std::time::Duration;
+
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+use crate::{bridge, osc};
+
+/// Check whether a process looks like Ableton Live.
+///
+/// - Windows: `Ableton Live 12 Suite.exe`, `Ableton Live 11 Lite.exe`, ...
+/// - macOS: the binary is just `Live`, inside `Ableton Live 12 Suite.app`
+/// - Linux: Live under Wine shows up with its Windows executable name
+///
+/// Names are compared case-insensitively and may be truncated (Linux limits
+/// process names to 15 bytes, e.g. `Ableton Live 12`).
+pub fn is_live_process(name: &str, exe: Option<&Path>) -> bool {
+    let name = name.to_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name);
+
+    if name.starts_with("ableton live") {
+        return true;
+    }
+
+    // A bare `Live` binary only counts when it sits inside an Ableton app bundle
+    name == "live"
+        && exe.is_some_and(|exe| {
+            exe.components().any(|c| {
+                let c = c.as_os_str().to_string_lossy().to_lowercase();
+                c.starts_with("ableton live") && c.ends_with(".app")
+            })
+        })
+}
+
+/// Check whether Ableton Live is currently running
+pub fn is_live_running() -> bool {
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_exe(UpdateKind::OnlyIfNotSet),
+    );
+
+    system
+        .processes()
+        .values()
+        .any(|p| is_live_process(&p.name().to_string_lossy(), p.exe()))
+}
+
+/// Check whether AbletonOSC on `osc_host` is answering, and which Live version
+/// it reports.
+///
+/// Probes AbletonOSC directly while the bridge is down. Otherwise the bridge owns
+/// the reply port, so ask it instead (it pings on its own schedule but can't
+/// tell us the Live version). Pass `bridge_running` as soon as the bridge has
+/// been started, so a probe can't take the port while it's still starting up.
+pub fn probe_script(ui_port: u16, osc_host: &str, bridge_running: bool) -> osc::Probe {
+    if !bridge_running {
+        match osc::probe(osc_host, Duration::from_secs(1)) {
+            Ok(probe) => return probe,
+            // A bridge started outside the app (e.g. in dev mode) holds the port
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {}
+            Err(e) => {
+                log::error!("[ableton] Probe failed: {}", e);
+                return osc::Probe::default();
+            }
+        }
+    }
+
+    osc::Probe {
+        responding: bridge::status(ui_port, Duration::from_secs(2)).is_ok_and(|s| s.ableton_connected),
+        live_version: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_live_on_each_platform() {
+        assert!(is_live_process("Ableton Live 12 Suite.exe", None));
+        assert!(is_live_process("ABLETON LIVE 11 LITE.EXE", None));
+        // Linux truncates process names to 15 bytes
+        assert!(is_live_process("Ableton Live 12", None));
+        assert!(is_live_process(
+            "Live",
+            Some(Path::new("/Applications/Ableton Live 12 Suite.app/Contents/MacOS/Live"))
+        ));
+    }
+
+    #[test]
+    fn ignores_other_processes() {
+        assert!(!is_live_process("Live", None));
+        assert!(!is_live_process("Live", Some(Path::new("/Applications/Live Streamer.app/Contents/MacOS/Live"))));
+        assert!(!is_live_process("Live", Some(Path::new("/usr/local/bin/Live"))));
+        assert!(!is_live_process("Ableton Index", None));
+        assert!(!is_live_process("Live.exe", Some(Path::new("C:\\Program Files\\Live\\Live.exe"))));
+        assert!(!is_live_process("livestream.exe", None));
+        assert!(!is_live_process("Mission Control Bridge.exe", None));
+    }
+
+    /// A copy of `sleep` named like Live stands in for it
+    #[cfg(unix)]
+    #[test]
+    fn finds_a_running_live_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let fake = dir.path().join("Ableton Live 12 Suite");
+        std::fs::copy("/bin/sleep", &fake).unwrap();
+
+        let mut live = std::process::Command::new(&fake).arg("30").spawn().unwrap();
+        let found = is_live_running();
+        live.kill().unwrap();
+        live.wait().unwrap();
+
+        assert!(found);
+        assert!(!is_live_running());
+    }
+}
@@ -0,0 +1,100 @@
+//! Helpers for querying the running bridge
+
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpStream};
+use std::time::Duration;
+
+use log::LevelFilter;
+pub use mission_control_protocol::BridgeStatus;
+
+use crate::osc;
+use crate::settings::BridgeSettings;
+
+/// What the app reads from the bridge's status
+pub trait BridgeStatusExt {
+    /// Someone is performing: Live is playing or another device is connected
+    fn show_in_progress(&self) -> bool;
+}
+
+impl BridgeStatusExt for BridgeStatus {
+    fn show_in_progress(&self) -> bool {
+        self.is_playing || self.remote_clients > 0
+    }
+}
+
+/// Where the bridge sends to AbletonOSC and listens for its replies
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OscRoute {
+    pub host: String,
+    pub send_port: u16,
+    pub receive_port: u16,
+}
+
+impl OscRoute {
+    /// Straight to AbletonOSC on `host`
+    pub fn direct(host: &str) -> OscRoute {
+        OscRoute {
+            host: host.to_string(),
+            send_port: osc::OSC_SEND_PORT,
+            receive_port: osc::OSC_RECEIVE_PORT,
+        }
+    }
+
+    /// The bridge's flags for this route (see server/standalone.ts)
+    pub fn args(&self) -> Vec<String> {
+        vec![
+            "--osc-host".to_string(),
+            self.host.clone(),
+            "--osc-send-port".to_string(),
+            self.send_port.to_string(),
+            "--osc-receive-port".to_string(),
+            self.receive_port.to_string(),
+        ]
+    }
+}
+
+/// Command-line arguments for starting the bridge (see server/standalone.ts),
+/// talking to AbletonOSC through `osc`
+#[cfg_attr(debug_assertions, allow(dead_code))]
+pub fn args(settings: &BridgeSettings, osc: &OscRoute, log_level: LevelFilter) -> Vec<String> {
+    let mut args = vec!["--port".to_string(), settings.ui_port.to_string()];
+    args.extend(osc.args());
+    args.extend(["--log-level".to_string(), log_level.to_string().to_lowercase()]);
+    args
+}
+
+/// Fetch the bridge's status from `GET /api/status` on `port`
+pub fn status(port: u16, timeout: Duration) -> Result<BridgeStatus, String> {
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)
+        .map_err(|e| format!("Could not connect to bridge: {}", e))?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .map_err(|e| format!("Could not set read timeout: {}", e))?;
+
+    // HTTP/1.0 so the bridge closes the connection after responding
+    write!(stream, "GET /api/status HTTP/1.0\r\nHost: {}\r\n\r\n", addr)
+        .map_err(|e| format!("Could not send status request: {}", e))?;
+
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| format!("Could not read status response: {}", e))?;
+
+    parse_status_response(&response)
+}
+
+/// Parse a raw HTTP response carrying a `BridgeStatus` body
+pub fn parse_status_response(response: &str) -> Result<BridgeStatus, String> {
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("Malformed status response")?;
+
+    let status_line = head.lines().next().unwrap_or_default();
+    if status_line.split_whitespace().nth(1) != Some("200") {
+        return Err(format!("Bridge returned {}", status_line));
+    }
+
+    serde_json::from_str(body).map_err(|e| format!("Could not parse bridge status: {}", e))
+}
@@ -0,0 +1,76 @@
+//! The app's own WebSocket connection to the bridge, for native controls
+//!
+//! Keeps a live copy of the session (see the mission-control-protocol crate)
+//! and hands changes to the tray, the MIDI controller's pads and the OSC relay.
+
+use std::sync::Mutex;
+
+use mission_control_protocol::{url_for_port, Client, ClientMessage, Snapshot};
+use tauri::{AppHandle, Manager};
+
+use crate::{midi, osc_relay, scene_menu, transport_menu};
+
+#[derive(Default)]
+pub struct BridgeClient {
+    client: Mutex<Option<Client>>,
+}
+
+/// Connect to the bridge on the current port, replacing any earlier connection
+pub fn start(app: &AppHandle) {
+    let port = crate::ui_port(app);
+
+    // The client spawns its task on the current Tokio runtime
+    let runtime = tauri::async_runtime::handle();
+    let _guard = runtime.inner().enter();
+    let client = Client::connect(url_for_port(port));
+    follow(app, client.watch());
+
+    *app.state::<BridgeClient>().client.lock().unwrap() = Some(client);
+    log::info!("[bridge-client] Connecting on port {}", port);
+}
+
+/// Send a message to the bridge
+pub fn send(app: &AppHandle, message: ClientMessage) -> Result<(), String> {
+    match app.state::<BridgeClient>().client.lock().unwrap().as_ref() {
+        Some(client) => client.send(message),
+        None => Err("Not connected to the bridge".to_string()),
+    }
+}
+
+/// Read the latest state without cloning the session. `None` before the
+/// client starts.
+pub fn with_snapshot<T>(app: &AppHandle, read: impl FnOnce(&Snapshot) -> T) -> Option<T> {
+    let state = app.state::<BridgeClient>();
+    let client = state.client.lock().unwrap();
+    client.as_ref().map(|client| read(&client.watch().borrow()))
+}
+
+/// Update the tray, pads and relay as the state changes, until the client is replaced
+fn follow(app: &AppHandle, mut watch: tokio::sync::watch::Receiver<Snapshot>) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let (transport, scenes, leds, relayed) = {
+                let snapshot = watch.borrow_and_update();
+                (
+                    transport_menu::View::of(&snapshot),
+                    scene_menu::View::of(&snapshot),
+                    midi::leds(&app, &snapshot),
+                    osc_relay::observe(&app, &snapshot),
+                )
+            };
+            transport_menu::update(&app, transport);
+            scene_menu::update(&app, scenes);
+            if let Some(leds) = leds {
+                midi::show(&app, leds);
+            }
+            if let Some(observed) = relayed {
+                osc_relay::relay_changes(&app, observed);
+            }
+
+            if watch.changed().await.is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,391 @@
+//! Command-line flags and subcommands
+//!
+//! Flags override the settings file for one launch without changing it.
+//! Subcommands run without the tray and exit with one of the `EXIT_*` codes.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use log::LevelFilter;
+use serde_json::json;
+
+use crate::settings::Settings;
+use crate::{ableton, bridge, network, osc_capture, remote_script};
+
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+/// Invalid flags (also what clap exits with for usage errors)
+pub const EXIT_USAGE: i32 = 2;
+/// `status`: the bridge isn't running
+pub const EXIT_BRIDGE_DOWN: i32 = 3;
+/// `status`: the bridge is running but AbletonOSC isn't answering
+pub const EXIT_ABLETON_DOWN: i32 = 4;
+
+#[derive(Debug, Clone, Parser)]
+#[command(version, about = "Control Ableton Live from your browser")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Port for the web UI and WebSocket
+    #[arg(long, global = true)]
+    pub port: Option<u16>,
+
+    /// Host running Ableton Live with AbletonOSC
+    #[arg(long, global = true, value_name = "HOST")]
+    pub osc_host: Option<String>,
+
+    /// Don't open Mission Control at launch, even if settings say to
+    #[arg(long, conflicts_with = "open_ui")]
+    pub no_browser: bool,
+
+    /// Open Mission Control at launch
+    #[arg(long)]
+    pub open_ui: bool,
+
+    /// Show the Connect Another Device window at launch
+    #[arg(long)]
+    pub show_qr: bool,
+
+    /// Don't check for updates in the background
+    #[arg(long)]
+    pub no_update_check: bool,
+
+    /// Show Mission Control full screen on a dedicated monitor, and keep it there
+    #[arg(long)]
+    pub kiosk: bool,
+
+    /// Monitor for kiosk mode, by name or number (from 1)
+    #[arg(long, value_name = "MONITOR")]
+    pub kiosk_monitor: Option<String>,
+
+    /// Run without the tray or windows (for servers): keep the bridge running,
+    /// advertise it on the network and stop on SIGTERM
+    #[arg(long, conflicts_with_all = ["open_ui", "show_qr", "kiosk"])]
+    pub headless: bool,
+
+    /// Record every OSC datagram between the bridge and AbletonOSC to a
+    /// timestamped file in DIR, for `replay-osc`
+    #[arg(long, value_name = "DIR", conflicts_with = "headless")]
+    pub osc_capture: Option<PathBuf>,
+
+    /// Log level: off, error, warn, info, debug or trace
+    #[arg(long, global = true, default_value = "info", value_name = "LEVEL")]
+    pub log_level: LevelFilter,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// Install the bundled AbletonOSC remote script into Live's User Library
+    InstallScript,
+    /// Remove AbletonOSC from Live's User Library
+    UninstallScript,
+    /// Report whether the bridge and AbletonOSC are running (exits 0, 3 or 4)
+    Status {
+        /// Print the status as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the URLs for opening Mission Control
+    PrintUrl,
+    /// Replay an OSC capture to the bridge on this machine, as if Ableton Live
+    /// were running
+    ReplayOsc {
+        /// A file recorded with --osc-capture
+        file: PathBuf,
+    },
+}
+
+impl Cli {
+    /// Apply flags on top of the settings file
+    pub fn apply(&self, settings: &mut Settings) {
+        if let Some(port) = self.port {
+            settings.bridge.ui_port = port;
+        }
+        if let Some(host) = &self.osc_host {
+            settings.bridge.osc_host = host.clone();
+        }
+        if self.open_ui {
+            settings.ui.open_on_launch = true;
+        }
+        if self.no_browser {
+            settings.ui.open_on_launch = false;
+        }
+        if self.kiosk {
+            settings.ui.kiosk = true;
+        }
+        if let Some(monitor) = &self.kiosk_monitor {
+            settings.ui.kiosk_monitor = Some(monitor.clone());
+        }
+        if self.no_update_check {
+            settings.updates.automatic = false;
+        }
+    }
+
+    /// Validate the flags that set values, returning messages naming the flag
+    pub fn validate(&self, settings: &Settings) -> Vec<String> {
+        settings
+            .validate()
+            .into_iter()
+            .filter_map(|error| {
+                let flag = match error.field.as_ref() {
+                    "bridge.uiPort" if self.port.is_some() => "--port",
+                    "bridge.oscHost" if self.osc_host.is_some() => "--osc-host",
+                    _ => return None,
+                };
+                Some(format!("Invalid {}: {}", flag, error.message))
+            })
+            .collect()
+    }
+}
+
+/// Print to the terminal that started the app, if any. Windows release builds
+/// are GUI apps with no console of their own, so subcommands and clap's
+/// messages would print nothing.
+pub fn attach_console() {
+    #[cfg(all(windows, not(debug_assertions)))]
+    {
+        const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+        #[link(name = "kernel32")]
+        extern "system" {
+            fn AttachConsole(process_id: u32) -> i32;
+        }
+        // Fails without a parent console, e.g. when started from the Start menu
+        unsafe {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
+
+/// Run a subcommand and return the process exit code
+pub fn run(command: &Command, settings: &Settings, ui_port: u16, resource_dir: Result<PathBuf, String>) -> i32 {
+    let result = match command {
+        Command::InstallScript => install_script(settings, resource_dir),
+        Command::UninstallScript => uninstall_script(settings),
+        Command::Status { json } => return status(settings, ui_port, *json),
+        Command::PrintUrl => {
+            print_urls(ui_port);
+            Ok(())
+        }
+        Command::ReplayOsc { file } => replay_osc(file),
+    };
+
+    match result {
+        Ok(()) => EXIT_OK,
+        Err(e) => {
+            log::error!("{}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+fn install_script(settings: &Settings, resource_dir: Result<PathBuf, String>) -> Result<(), String> {
+    let remote_scripts = remote_script::remote_scripts_path(settings)?;
+    let path = remote_script::install(&resource_dir?, &remote_scripts)?;
+    println!("Installed AbletonOSC to {}", path.display());
+
+    if ableton::is_live_running() {
+        println!("Ableton Live is running. Restart it to load the script.");
+    }
+    Ok(())
+}
+
+fn uninstall_script(settings: &Settings) -> Result<(), String> {
+    let remote_scripts = remote_script::remote_scripts_path(settings)?;
+    match remote_script::uninstall(&remote_scripts)? {
+        Some(path) => println!("Removed AbletonOSC from {}", path.display()),
+        None => println!("AbletonOSC is not installed in {}", remote_scripts.display()),
+    }
+    Ok(())
+}
+
+fn replay_osc(file: &Path) -> Result<(), String> {
+    let count = osc_capture::replay(file)?;
+    println!("Replayed {} datagrams from {}", count, file.display());
+    Ok(())
+}
+
+fn status(settings: &Settings, ui_port: u16, as_json: bool) -> i32 {
+    let bridge = bridge::status(ui_port, Duration::from_secs(2)).ok();
+    let probe = ableton::probe_script(ui_port, &settings.bridge.osc_host, bridge.is_some());
+    let remote_scripts = remote_script::remote_scripts_path(settings).ok();
+    let installed = remote_scripts.as_deref().is_some_and(remote_script::is_installed);
+
+    if as_json {
+        let status = json!({
+            "bridge": {
+                "running": bridge.is_some(),
+                "port": ui_port,
+                "clients": bridge.as_ref().map(|b| b.clients),
+                "remoteClients": bridge.as_ref().map(|b| b.remote_clients),
+                "playing": bridge.as_ref().map(|b| b.is_playing),
+            },
+            "ableton": {
+                "host": settings.bridge.osc_host,
+                "responding": probe.responding,
+                "liveVersion": probe.live_version,
+            },
+            "script": {
+                "installed": installed,
+                "path": remote_scripts,
+            },
+        });
+        println!("{}", serde_json::to_string_pretty(&status).unwrap_or_default());
+    } else {
+        match &bridge {
+            Some(b) => println!(
+                "Bridge:     running on port {} ({} clients, {} remote)",
+                ui_port, b.clients, b.remote_clients
+            ),
+            None => println!("Bridge:     not running on port {}", ui_port),
+        }
+        match (probe.responding, &probe.live_version) {
+            (true, Some(version)) => println!("Ableton:    AbletonOSC responding on {} (Live {})", settings.bridge.osc_host, version),
+            (true, None) => println!("Ableton:    AbletonOSC responding on {}", settings.bridge.osc_host),
+            (false, _) => println!("Ableton:    AbletonOSC not responding on {}", settings.bridge.osc_host),
+        }
+        match (&remote_scripts, installed) {
+            (Some(path), true) => println!("AbletonOSC: installed in {}", path.display()),
+            (Some(path), false) => println!("AbletonOSC: not installed in {}", path.display()),
+            (None, _) => println!("AbletonOSC: unknown Remote Scripts folder"),
+        }
+    }
+
+    match (&bridge, probe.responding) {
+        (None, _) => EXIT_BRIDGE_DOWN,
+        (Some(_), false) => EXIT_ABLETON_DOWN,
+        (Some(_), true) => EXIT_OK,
+    }
+}
+
+fn print_urls(ui_port: u16) {
+    for url in network::ui_urls(ui_port) {
+        println!("{}", url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::net::{Ipv4Addr, TcpListener};
+
+    use clap::error::ErrorKind;
+
+    use crate::remote_script::SCRIPT_NAME;
+
+    fn parse(args: &[&str]) -> Result<Cli, clap::Error> {
+        Cli::try_parse_from(std::iter::once("mission-control").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn flags_override_settings() {
+        let cli = parse(&["--port", "6000", "--osc-host", "studio.local", "--no-browser", "--kiosk", "--kiosk-monitor", "2", "--no-update-check"]).unwrap();
+        let mut settings = Settings::default();
+        settings.ui.open_on_launch = true;
+        cli.apply(&mut settings);
+
+        assert_eq!(settings.bridge.ui_port, 6000);
+        assert_eq!(settings.bridge.osc_host, "studio.local");
+        assert!(!settings.ui.open_on_launch);
+        assert!(settings.ui.kiosk);
+        assert_eq!(settings.ui.kiosk_monitor.as_deref(), Some("2"));
+        assert!(!settings.updates.automatic);
+    }
+
+    #[test]
+    fn settings_stand_without_flags() {
+        let mut settings = Settings::default();
+        settings.bridge.ui_port = 6000;
+        settings.ui.open_on_launch = true;
+        let expected = settings.clone();
+        parse(&[]).unwrap().apply(&mut settings);
+        assert_eq!(settings, expected);
+    }
+
+    #[test]
+    fn global_flags_work_after_subcommands() {
+        let cli = parse(&["status", "--json", "--port", "6000"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Status { json: true })));
+        assert_eq!(cli.port, Some(6000));
+    }
+
+    #[test]
+    fn rejects_conflicting_flags() {
+        for args in [
+            &["--no-browser", "--open-ui"][..],
+            &["--headless", "--show-qr"],
+            &["--headless", "--kiosk"],
+            &["--headless", "--osc-capture", "captures"],
+        ] {
+            let error = parse(args).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::ArgumentConflict, "{:?}", args);
+            assert_eq!(error.exit_code(), EXIT_USAGE);
+        }
+    }
+
+    #[test]
+    fn names_the_flag_that_set_an_invalid_value() {
+        let cli = parse(&["--port", "80"]).unwrap();
+        let mut settings = Settings::default();
+        cli.apply(&mut settings);
+        let errors = cli.validate(&settings);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].starts_with("Invalid --port: "), "{}", errors[0]);
+
+        // Errors from the settings file aren't the flags' fault
+        settings.bridge.ui_port = 80;
+        assert!(parse(&[]).unwrap().validate(&settings).is_empty());
+    }
+
+    #[test]
+    fn installs_and_uninstalls_the_script() {
+        let resources = tempfile::tempdir().unwrap();
+        fs::create_dir_all(resources.path().join(SCRIPT_NAME).join("abletonosc")).unwrap();
+        fs::write(resources.path().join(SCRIPT_NAME).join("__init__.py"), "").unwrap();
+        fs::write(resources.path().join(SCRIPT_NAME).join("abletonosc").join("song.py"), "").unwrap();
+
+        let library = tempfile::tempdir().unwrap();
+        let remote_scripts = library.path().join("Remote Scripts");
+        let mut settings = Settings::default();
+        settings.ableton.remote_scripts_path = Some(remote_scripts.clone());
+
+        let install = |settings: &Settings| run(&Command::InstallScript, settings, 0, Ok(resources.path().to_path_buf()));
+        assert_eq!(install(&settings), EXIT_OK);
+        assert!(remote_scripts.join(SCRIPT_NAME).join("abletonosc").join("song.py").is_file());
+        // Installing again replaces the copy
+        fs::write(remote_scripts.join(SCRIPT_NAME).join("stale.py"), "").unwrap();
+        assert_eq!(install(&settings), EXIT_OK);
+        assert!(!remote_scripts.join(SCRIPT_NAME).join("stale.py").exists());
+
+        assert_eq!(run(&Command::UninstallScript, &settings, 0, Err(String::new())), EXIT_OK);
+        assert!(!remote_scripts.join(SCRIPT_NAME).exists());
+        // Uninstalling what isn't there is fine
+        assert_eq!(run(&Command::UninstallScript, &settings, 0, Err(String::new())), EXIT_OK);
+    }
+
+    #[test]
+    fn exits_with_failure_when_a_command_fails() {
+        let library = tempfile::tempdir().unwrap();
+        let mut settings = Settings::default();
+        settings.ableton.remote_scripts_path = Some(library.path().to_path_buf());
+
+        let no_resources = Err("Could not get resource dir".to_string());
+        assert_eq!(run(&Command::InstallScript, &settings, 0, no_resources), EXIT_FAILURE);
+        let missing = Command::ReplayOsc {
+            file: library.path().join("missing.jsonl"),
+        };
+        assert_eq!(run(&missing, &settings, 0, Err(String::new())), EXIT_FAILURE);
+    }
+
+    #[test]
+    fn status_exits_with_bridge_down() {
+        // A port nothing listens on
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap().port();
+        let mut settings = Settings::default();
+        // Nothing answers OSC here either
+        settings.bridge.osc_host = "127.0.0.1".to_string();
+        assert_eq!(run(&Command::Status { json: true }, &settings, port, Err(String::new())), EXIT_BRIDGE_DOWN);
+    }
+}
@@ -0,0 +1,165 @@
+//! Commands and events for webviews showing Mission Control
+//!
+//! Only the windows listed in capabilities/default.json can call these. Each
+//! command also needs an `allow-<command>` permission there (see build.rs).
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{ableton, bridge, link, network, osc, remote_script, AppState};
+
+/// Emitted with an [`AppStatus`] whenever any part of it changes
+pub const STATUS_CHANGED: &str = "status-changed";
+
+/// Emitted with a [`link::LinkStatus`] when Link is turned on or off, or its
+/// tempo, peers or playing change. The beat moves on without it.
+pub const LINK_CHANGED: &str = "link-changed";
+
+/// Everything the UI can show about the app, from [`get_status`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppStatus {
+    pub version: String,
+    pub port: u16,
+    /// `None` if the bridge isn't answering
+    pub bridge: Option<bridge::BridgeStatus>,
+    pub ableton: osc::Probe,
+    /// URLs other devices can open, LAN addresses first
+    pub urls: Vec<String>,
+    pub script_installed: bool,
+    /// Live must restart to load a freshly installed AbletonOSC
+    pub restart_required: bool,
+    /// Version of an available update, once a check has found one
+    pub update_available: Option<String>,
+}
+
+/// Collect the current status. Blocks for up to a couple of seconds asking the
+/// bridge for its status.
+pub fn status(app: &AppHandle) -> AppStatus {
+    let state = app.state::<AppState>();
+    let settings = crate::effective_settings(app);
+    let port = crate::ui_port_of(&settings);
+
+    AppStatus {
+        version: app.package_info().version.to_string(),
+        port,
+        bridge: bridge::status(port, Duration::from_secs(2)).ok(),
+        ableton: state.ableton_status.lock().unwrap().clone(),
+        urls: network::ui_urls(port),
+        script_installed: remote_script::remote_scripts_path(&settings).is_ok_and(|path| remote_script::is_installed(&path)),
+        restart_required: *state.restart_required.lock().unwrap(),
+        update_available: state.update_available.lock().unwrap().clone(),
+    }
+}
+
+/// Emit [`STATUS_CHANGED`] if the status differs from the last one emitted
+pub fn emit_status(app: &AppHandle) {
+    let status = status(app);
+    let state = app.state::<AppState>();
+    let mut last = state.last_status.lock().unwrap();
+    if last.as_ref() == Some(&status) {
+        return;
+    }
+
+    if let Err(e) = app.emit(STATUS_CHANGED, &status) {
+        log::error!("Failed to emit status: {}", e);
+    }
+    *last = Some(status);
+}
+
+/// [`emit_status`] without blocking the caller
+pub fn emit_status_later(app: &AppHandle) {
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || emit_status(&handle));
+}
+
+#[tauri::command]
+pub async fn get_status(app: AppHandle) -> Result<AppStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || status(&app))
+        .await
+        .map_err(|e| format!("Could not get status: {}", e))
+}
+
+/// The Ableton Link session, which doesn't need the bridge
+#[tauri::command]
+pub fn get_link_status(app: AppHandle) -> link::LinkStatus {
+    link::status(&app)
+}
+
+/// URLs other devices can open Mission Control at, LAN addresses first
+#[tauri::command]
+pub fn get_urls(app: AppHandle) -> Vec<String> {
+    network::ui_urls(crate::ui_port(&app))
+}
+
+/// QR code for `text` as a base64 PNG
+#[tauri::command]
+pub fn get_qr_code(text: String) -> Result<String, String> {
+    crate::generate_qr_code_base64(&text)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallResult {
+    pub path: String,
+    /// Live was running, so it must restart to load the script
+    pub restart_required: bool,
+}
+
+/// Install (or reinstall) AbletonOSC, like the tray's Install AbletonOSC
+#[tauri::command]
+pub async fn install_script(app: AppHandle) -> Result<InstallResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = crate::install_remote_script(&app)?;
+
+        // Live only loads remote scripts at startup
+        let restart_required = ableton::is_live_running();
+        if restart_required {
+            crate::set_restart_required(&app, true);
+        }
+        emit_status(&app);
+
+        Ok(InstallResult { path, restart_required })
+    })
+    .await
+    .map_err(|e| format!("Could not install AbletonOSC: {}", e))?
+}
+
+/// Restart the bridge. Connected devices reconnect on their own.
+#[tauri::command]
+pub async fn restart_bridge(app: AppHandle) -> Result<(), String> {
+    if cfg!(debug_assertions) {
+        return Err("Restart `bun run server/standalone.ts` to restart the dev bridge".to_string());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::restart_bridge(&app);
+        emit_status(&app);
+    })
+    .await
+    .map_err(|e| format!("Could not restart bridge: {}", e))
+}
+
+/// Check for updates like the tray's Check for Updates..., prompting if one is
+/// available. Returns once the check has started; watch [`STATUS_CHANGED`] for
+/// `updateAvailable`.
+#[tauri::command]
+pub fn check_updates(app: AppHandle) -> Result<(), String> {
+    #[cfg(not(debug_assertions))]
+    {
+        tauri::async_runtime::spawn(async move {
+            if let Err(e) = crate::check_for_updates(app, true).await {
+                log::error!("Update check failed: {}", e);
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(debug_assertions)]
+    {
+        let _ = app;
+        Err("Updates are disabled in development builds".to_string())
+    }
+}
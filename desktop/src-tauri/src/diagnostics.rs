@@ -0,0 +1,405 @@
+//! Port conflict diagnostics for the OSC and UI ports
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::net::{Ipv4Addr, TcpListener, UdpSocket};
+use std::path::PathBuf;
+
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+use crate::{ableton, osc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    Udp,
+    Tcp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Protocol::Udp => write!(f, "UDP"),
+            Protocol::Tcp => write!(f, "TCP"),
+        }
+    }
+}
+
+/// What a port is supposed to be used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortRole {
+    /// AbletonOSC inside Live listens here
+    AbletonOsc,
+    /// The bridge receives AbletonOSC replies here
+    BridgeOsc,
+    /// The bridge serves the web UI and WebSocket here
+    BridgeUi,
+}
+
+impl PortRole {
+    fn label(&self) -> &'static str {
+        match self {
+            PortRole::AbletonOsc => "AbletonOSC",
+            PortRole::BridgeOsc => "bridge OSC",
+            PortRole::BridgeUi => "web UI",
+        }
+    }
+
+    fn is_expected_owner(&self, owner: &PortOwner) -> bool {
+        // Our own probes briefly hold the OSC reply port
+        if owner.pid == std::process::id() {
+            return true;
+        }
+
+        match self {
+            PortRole::AbletonOsc => ableton::is_live_process(&owner.name, owner.exe.as_deref()),
+            PortRole::BridgeOsc | PortRole::BridgeUi => is_bridge_process(&owner.name),
+        }
+    }
+}
+
+/// Check whether a process is the bridge: the bundled sidecar, or bun/node
+/// running it (or Vite) in dev mode. Linux truncates process names to 15 bytes.
+fn is_bridge_process(name: &str) -> bool {
+    let name = name.to_lowercase();
+    let name = name.strip_suffix(".exe").unwrap_or(&name);
+    name.starts_with("mission-control") || name == "bun" || name == "node"
+}
+
+/// A process holding a port
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PortOwner {
+    pub pid: u32,
+    pub name: String,
+    pub exe: Option<PathBuf>,
+}
+
+impl fmt::Display for PortOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (PID {})", self.name, self.pid)
+    }
+}
+
+/// The result of checking a single port
+#[derive(Debug, Clone)]
+pub struct PortReport {
+    pub protocol: Protocol,
+    pub port: u16,
+    pub role: PortRole,
+    pub in_use: bool,
+    pub owners: Vec<PortOwner>,
+}
+
+impl PortReport {
+    /// Owners that have no business holding this port
+    pub fn conflicts(&self) -> Vec<&PortOwner> {
+        self.owners.iter().filter(|o| !self.role.is_expected_owner(o)).collect()
+    }
+
+    /// One-line summary, e.g. "UDP 11000 (AbletonOSC): Live (PID 123)"
+    pub fn describe(&self) -> String {
+        let holder = if !self.in_use {
+            "free".to_string()
+        } else if self.owners.is_empty() {
+            "in use by an unknown process".to_string()
+        } else {
+            self.owners.iter().map(|o| o.to_string()).collect::<Vec<_>>().join(", ")
+        };
+        format!("{} {} ({}): {}", self.protocol, self.port, self.role.label(), holder)
+    }
+
+    /// What the user should do about this port, if anything
+    pub fn problem(&self) -> Option<String> {
+        let conflicts = self.conflicts();
+        if !conflicts.is_empty() {
+            let holders = conflicts.iter().map(|o| o.to_string()).collect::<Vec<_>>().join(", ");
+            let names = conflicts.iter().map(|o| o.name.as_str()).collect::<Vec<_>>().join(", ");
+            return Some(match self.role {
+                PortRole::AbletonOsc => format!(
+                    "{} {} is held by {}, so AbletonOSC can't receive commands (Live shows \"Couldn't bind to port\"). Quit {} and restart Ableton Live.",
+                    self.protocol, self.port, holders, names
+                ),
+                PortRole::BridgeOsc => format!(
+                    "{} {} is held by {}, so the bridge can't receive replies from Ableton. Quit {} and restart Mission Control.",
+                    self.protocol, self.port, holders, names
+                ),
+                PortRole::BridgeUi => format!(
+                    "{} {} is held by {}, so the Mission Control UI can't be served. Quit {} and restart Mission Control.",
+                    self.protocol, self.port, holders, names
+                ),
+            });
+        }
+
+        if self.in_use && self.owners.is_empty() {
+            return Some(format!(
+                "{} {} is in use by a process we can't identify (it may belong to another user). Free the port and restart Mission Control.",
+                self.protocol, self.port
+            ));
+        }
+
+        if !self.in_use {
+            return Some(match self.role {
+                PortRole::AbletonOsc => format!(
+                    "Nothing is listening on {} {}, so AbletonOSC isn't loaded. Open Ableton Live and select AbletonOSC in Preferences → Link, Tempo & MIDI → Control Surface.",
+                    self.protocol, self.port
+                ),
+                PortRole::BridgeOsc | PortRole::BridgeUi => format!(
+                    "Nothing is listening on {} {}, so the bridge isn't running. Restart Mission Control.",
+                    self.protocol, self.port
+                ),
+            });
+        }
+
+        None
+    }
+}
+
+/// Check the AbletonOSC ports and the UI port
+pub fn check_ports(ui_port: u16) -> Vec<PortReport> {
+    [
+        (Protocol::Udp, osc::OSC_SEND_PORT, PortRole::AbletonOsc),
+        (Protocol::Udp, osc::OSC_RECEIVE_PORT, PortRole::BridgeOsc),
+        (Protocol::Tcp, ui_port, PortRole::BridgeUi),
+    ]
+    .into_iter()
+    .map(|(protocol, port, role)| check_port(protocol, port, role))
+    .collect()
+}
+
+/// Check a single port, identifying whoever holds it
+pub fn check_port(protocol: Protocol, port: u16, role: PortRole) -> PortReport {
+    let owners = find_owners(protocol, port);
+    // Sockets we can't see (e.g. other users' on macOS) still block binding
+    let in_use = !owners.is_empty() || !can_bind(protocol, port);
+    PortReport { protocol, port, role, in_use, owners }
+}
+
+/// Format a full report for display
+pub fn summary(reports: &[PortReport]) -> String {
+    let lines: Vec<String> = reports.iter().map(|r| r.describe()).collect();
+    let problems: Vec<String> = reports.iter().filter_map(|r| r.problem()).collect();
+
+    if problems.is_empty() {
+        format!("{}\n\nNo port problems found.", lines.join("\n"))
+    } else {
+        format!("{}\n\n• {}", lines.join("\n"), problems.join("\n\n• "))
+    }
+}
+
+fn can_bind(protocol: Protocol, port: u16) -> bool {
+    match protocol {
+        Protocol::Udp => UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok(),
+        Protocol::Tcp => TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok(),
+    }
+}
+
+/// Find the processes holding a port by matching socket inodes from
+/// `/proc/net/*` against each process's open file descriptors
+#[cfg(target_os = "linux")]
+fn find_owners(protocol: Protocol, port: u16) -> Vec<PortOwner> {
+    use std::fs;
+
+    let tables = match protocol {
+        Protocol::Udp => ["/proc/net/udp", "/proc/net/udp6"],
+        Protocol::Tcp => ["/proc/net/tcp", "/proc/net/tcp6"],
+    };
+
+    let inodes: BTreeSet<u64> = tables
+        .iter()
+        .filter_map(|table| fs::read_to_string(table).ok())
+        .flat_map(|contents| parse_proc_net(&contents, protocol, port))
+        .collect();
+
+    if inodes.is_empty() {
+        return Vec::new();
+    }
+
+    let Ok(procs) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut pids = BTreeSet::new();
+    for entry in procs.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<u32>().ok()) else {
+            continue;
+        };
+        // Other users' processes aren't readable without privileges
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+
+        let holds_socket = fds.flatten().any(|fd| {
+            fs::read_link(fd.path())
+                .ok()
+                .and_then(|target| {
+                    let target = target.to_string_lossy().into_owned();
+                    target.strip_prefix("socket:[")?.strip_suffix(']')?.parse::<u64>().ok()
+                })
+                .is_some_and(|inode| inodes.contains(&inode))
+        });
+        if holds_socket {
+            pids.insert(pid);
+        }
+    }
+
+    describe_processes(pids)
+}
+
+/// Parse a `/proc/net/{tcp,udp}[6]` table and return the inodes of sockets
+/// bound to `port` (listening sockets only, for TCP)
+#[cfg(target_os = "linux")]
+pub fn parse_proc_net(contents: &str, protocol: Protocol, port: u16) -> Vec<u64> {
+    // sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode
+    const TCP_LISTEN: &str = "0A";
+
+    contents
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local_port = fields.get(1)?.rsplit(':').next()?;
+            let local_port = u16::from_str_radix(local_port, 16).ok()?;
+            let state = *fields.get(3)?;
+            let inode = fields.get(9)?.parse::<u64>().ok()?;
+
+            let listening = protocol == Protocol::Udp || state == TCP_LISTEN;
+            (local_port == port && listening && inode != 0).then_some(inode)
+        })
+        .collect()
+}
+
+/// Find the processes holding a port via the platform socket tables
+/// (libproc on macOS, the IP Helper API on Windows)
+#[cfg(not(target_os = "linux"))]
+fn find_owners(protocol: Protocol, port: u16) -> Vec<PortOwner> {
+    use netstat2::{get_sockets_info, AddressFamilyFlags, ProtocolFlags, ProtocolSocketInfo, TcpState};
+
+    let protocol_flags = match protocol {
+        Protocol::Udp => ProtocolFlags::UDP,
+        Protocol::Tcp => ProtocolFlags::TCP,
+    };
+
+    let sockets = match get_sockets_info(AddressFamilyFlags::IPV4 | AddressFamilyFlags::IPV6, protocol_flags) {
+        Ok(sockets) => sockets,
+        Err(e) => {
+            log::error!("[diagnostics] Could not list sockets: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let pids: BTreeSet<u32> = sockets
+        .into_iter()
+        .filter(|s| match &s.protocol_socket_info {
+            ProtocolSocketInfo::Tcp(tcp) => tcp.local_port == port && tcp.state == TcpState::Listen,
+            ProtocolSocketInfo::Udp(udp) => udp.local_port == port,
+        })
+        .flat_map(|s| s.associated_pids)
+        .collect();
+
+    describe_processes(pids)
+}
+
+fn describe_processes(pids: BTreeSet<u32>) -> Vec<PortOwner> {
+    let pids: Vec<Pid> = pids.into_iter().map(Pid::from_u32).collect();
+    let mut system = System::new();
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&pids),
+        true,
+        ProcessRefreshKind::nothing().with_exe(UpdateKind::OnlyIfNotSet),
+    );
+
+    pids.iter()
+        .map(|pid| {
+            let process = system.process(*pid);
+            PortOwner {
+                pid: pid.as_u32(),
+                name: process
+                    .map(|p| p.name().to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "unknown".to_string()),
+                exe: process.and_then(|p| p.exe()).map(|exe| exe.to_path_buf()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLES: [PortRole; 3] = [PortRole::AbletonOsc, PortRole::BridgeOsc, PortRole::BridgeUi];
+
+    fn report(role: PortRole, in_use: bool, owners: Vec<PortOwner>) -> PortReport {
+        PortReport { protocol: Protocol::Udp, port: 11000, role, in_use, owners }
+    }
+
+    fn owner(pid: u32, name: &str) -> PortOwner {
+        PortOwner { pid, name: name.to_string(), exe: None }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn finds_listening_sockets_in_proc_net() {
+        let table = include_str!("../tests/fixtures/proc_net_tcp");
+        // Connected sockets only count for UDP; inode 0 and malformed rows never do
+        assert_eq!(parse_proc_net(table, Protocol::Tcp, 11000), vec![41001]);
+        assert_eq!(parse_proc_net(table, Protocol::Udp, 11000), vec![41001, 41002]);
+        assert_eq!(parse_proc_net(table, Protocol::Tcp, 5555), vec![41005]);
+        assert!(parse_proc_net(table, Protocol::Tcp, 11001).is_empty());
+    }
+
+    #[test]
+    fn blames_foreign_owners() {
+        let messages = ROLES.map(|role| {
+            let report = report(role, true, vec![owner(4242, "Max.exe")]);
+            assert_eq!(report.conflicts(), vec![&owner(4242, "Max.exe")]);
+            report.problem().unwrap()
+        });
+        assert_eq!(
+            messages,
+            [
+                "UDP 11000 is held by Max.exe (PID 4242), so AbletonOSC can't receive commands (Live shows \"Couldn't bind to port\"). Quit Max.exe and restart Ableton Live.",
+                "UDP 11000 is held by Max.exe (PID 4242), so the bridge can't receive replies from Ableton. Quit Max.exe and restart Mission Control.",
+                "UDP 11000 is held by Max.exe (PID 4242), so the Mission Control UI can't be served. Quit Max.exe and restart Mission Control.",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_ports_held_by_someone_unknown() {
+        for role in ROLES {
+            let report = report(role, true, vec![]);
+            assert!(report.conflicts().is_empty());
+            assert_eq!(
+                report.problem().unwrap(),
+                "UDP 11000 is in use by a process we can't identify (it may belong to another user). Free the port and restart Mission Control."
+            );
+        }
+    }
+
+    #[test]
+    fn reports_ports_nobody_listens_on() {
+        let messages = ROLES.map(|role| report(role, false, vec![]).problem().unwrap());
+        assert_eq!(
+            messages,
+            [
+                "Nothing is listening on UDP 11000, so AbletonOSC isn't loaded. Open Ableton Live and select AbletonOSC in Preferences → Link, Tempo & MIDI → Control Surface.",
+                "Nothing is listening on UDP 11000, so the bridge isn't running. Restart Mission Control.",
+                "Nothing is listening on UDP 11000, so the bridge isn't running. Restart Mission Control.",
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_expected_owners() {
+        let ours = owner(std::process::id(), "mission-control");
+        for role in ROLES {
+            let report = report(role, true, vec![ours.clone()]);
+            assert!(report.conflicts().is_empty());
+            assert_eq!(report.problem(), None);
+        }
+
+        assert_eq!(report(PortRole::AbletonOsc, true, vec![owner(1, "Ableton Live 12 Suite.exe")]).problem(), None);
+        assert_eq!(report(PortRole::BridgeOsc, true, vec![owner(1, "mission-control-bridge")]).problem(), None);
+        assert_eq!(report(PortRole::BridgeUi, true, vec![owner(1, "bun")]).problem(), None);
+        assert!(report(PortRole::BridgeUi, true, vec![owner(1, "Ableton Live 12 Suite.exe")]).problem().is_some());
+    }
+}
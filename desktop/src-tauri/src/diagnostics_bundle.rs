@@ -0,0 +1,327 @@
+//! Diagnostics bundle: a zip of everything we usually ask for in bug reports
+//!
+//! Secrets (tokens, passwords, keys) are redacted from every text file in it.
+
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use sysinfo::System;
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::settings::Settings;
+use crate::{ableton, bridge, diagnostics, logging, network, remote_script};
+
+/// Only the end of a long AbletonOSC log is useful
+const MAX_SCRIPT_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Keys whose values are redacted, matched case-insensitively anywhere in a
+/// key, e.g. `pairingToken`
+const SECRET_KEYS: &[&str] = &["token", "secret", "password", "passwd", "apikey", "api_key", "authorization"];
+
+const REDACTED: &str = "[redacted]";
+
+/// What the bundle describes, beyond what it can find out itself
+pub struct Inputs {
+    pub app_version: String,
+    /// Settings in effect, including command-line overrides
+    pub settings: Settings,
+    pub ui_port: u16,
+    /// The app started the bridge, which may not be answering yet but needs
+    /// AbletonOSC's reply port
+    pub bridge_started: bool,
+    /// Where the bundled AbletonOSC is, to compare the installed copy against
+    pub resource_dir: Option<PathBuf>,
+}
+
+/// Collect diagnostics and write them to a zip at `path`
+pub fn write(path: &Path, inputs: &Inputs) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+
+    let remote_scripts = remote_script::remote_scripts_path(&inputs.settings);
+    let script_dir = remote_scripts.as_ref().ok().map(|path| path.join(remote_script::SCRIPT_NAME));
+
+    let mut files = vec![
+        ("summary.txt", summary(inputs)),
+        ("settings.json", serde_json::to_string_pretty(&inputs.settings).unwrap_or_default()),
+        ("ports.txt", diagnostics::summary(&diagnostics::check_ports(inputs.ui_port))),
+        ("network.txt", network_report(inputs.ui_port)),
+        ("remote-script.txt", script_report(&remote_scripts, inputs.resource_dir.as_deref())),
+        ("mission-control.log", logging::recent().join("\n")),
+    ];
+
+    let script_log = script_dir.map(|dir| dir.join("logs").join("abletonosc.log"));
+    if let Some(contents) = script_log.as_deref().and_then(read_tail) {
+        files.push(("abletonosc.log", contents));
+    }
+
+    for (name, contents) in files {
+        zip.start_file(name, SimpleFileOptions::default())
+            .and_then(|_| Ok(zip.write_all(redact(&contents).as_bytes())?))
+            .map_err(|e| format!("Could not write {} to the bundle: {}", name, e))?;
+    }
+
+    zip.finish().map_err(|e| format!("Could not write the bundle: {}", e))?;
+    Ok(())
+}
+
+fn summary(inputs: &Inputs) -> String {
+    let host = &inputs.settings.bridge.osc_host;
+    let bridge = bridge::status(inputs.ui_port, Duration::from_secs(2)).ok();
+    let probe = ableton::probe_script(inputs.ui_port, host, inputs.bridge_started || bridge.is_some());
+
+    let bridge_line = match &bridge {
+        Some(status) => format!(
+            "running on port {}, version {} ({} clients, {} remote, Ableton {})",
+            inputs.ui_port,
+            status.version.as_deref().unwrap_or("unknown"),
+            status.clients,
+            status.remote_clients,
+            if status.ableton_connected { "connected" } else { "not connected" },
+        ),
+        None => format!("not running on port {}", inputs.ui_port),
+    };
+
+    let live_line = match (ableton::is_live_running(), &probe.live_version) {
+        (true, Some(version)) => format!("running (Live {})", version),
+        (true, None) => "running".to_string(),
+        (false, _) => "not running".to_string(),
+    };
+
+    [
+        format!("Mission Control: {}", inputs.app_version),
+        format!("Bridge:          {}", bridge_line),
+        format!(
+            "OS:              {} (kernel {}, {})",
+            System::long_os_version().unwrap_or_else(|| std::env::consts::OS.to_string()),
+            System::kernel_version().unwrap_or_else(|| "unknown".to_string()),
+            std::env::consts::ARCH
+        ),
+        format!("Ableton Live:    {}", live_line),
+        format!(
+            "AbletonOSC:      {} on {}",
+            if probe.responding { "responding" } else { "not responding" },
+            host
+        ),
+    ]
+    .join("\n")
+}
+
+fn network_report(ui_port: u16) -> String {
+    let mut lines = vec!["Interfaces:".to_string()];
+    lines.extend(network::interfaces().into_iter().map(|line| format!("  {}", line)));
+    lines.push(String::new());
+    lines.push("UI URLs:".to_string());
+    lines.extend(network::ui_urls(ui_port).into_iter().map(|url| format!("  {}", url)));
+    lines.join("\n")
+}
+
+/// Where the script is, and a manifest of the installed files noting any that
+/// differ from the bundled copy
+fn script_report(remote_scripts: &Result<PathBuf, String>, resource_dir: Option<&Path>) -> String {
+    let remote_scripts = match remote_scripts {
+        Ok(path) => path,
+        Err(e) => return format!("Remote Scripts folder: unknown ({})", e),
+    };
+    let installed = remote_scripts.join(remote_script::SCRIPT_NAME);
+    let mut lines = vec![format!("Remote Scripts folder: {}", remote_scripts.display())];
+
+    if !installed.is_dir() {
+        lines.push("AbletonOSC: not installed".to_string());
+        return lines.join("\n");
+    }
+
+    lines.push(format!("AbletonOSC: installed in {}", installed.display()));
+    lines.push(String::new());
+
+    let bundled = resource_dir
+        .map(|dir| dir.join(remote_script::SCRIPT_NAME))
+        .filter(|dir| dir.is_dir());
+    for (relative, size) in manifest(&installed) {
+        // The log is written by the script, so never matches
+        if relative.starts_with("logs") {
+            continue;
+        }
+        let note = match bundled.as_ref().map(|dir| fs::metadata(dir.join(&relative))) {
+            None => "",
+            Some(Ok(metadata)) if metadata.len() == size => "",
+            Some(Ok(_)) => "  (differs from bundled)",
+            Some(Err(_)) => "  (not in bundled)",
+        };
+        lines.push(format!("{:>9}  {}{}", size, relative.display(), note));
+    }
+    lines.join("\n")
+}
+
+/// Relative path and size of every file under `dir`, sorted
+fn manifest(dir: &Path) -> Vec<(PathBuf, u64)> {
+    fn walk(root: &Path, dir: &Path, files: &mut Vec<(PathBuf, u64)>) {
+        let Ok(entries) = fs::read_dir(dir) else { return };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(metadata) = entry.metadata() else { continue };
+            if metadata.is_dir() {
+                // Compiled bytecode differs between Live versions, so leave it out
+                if entry.file_name() != "__pycache__" {
+                    walk(root, &path, files);
+                }
+            } else if let Ok(relative) = path.strip_prefix(root) {
+                files.push((relative.to_path_buf(), metadata.len()));
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files);
+    files.sort();
+    files
+}
+
+/// The last [`MAX_SCRIPT_LOG_BYTES`] of a file, or `None` if it can't be read
+fn read_tail(path: &Path) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(MAX_SCRIPT_LOG_BYTES))).ok()?;
+
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Replace the values of secret-looking keys, in `key=value`, `key: value` and
+/// JSON `"key": "value"` forms
+pub fn redact(text: &str) -> String {
+    // ASCII lowercasing keeps byte offsets the same
+    let lower = text.to_ascii_lowercase();
+    let bytes = text.as_bytes();
+    let mut redacted = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut search = 0;
+
+    while let Some((key_end, value_start, value_end)) = next_secret(&lower, bytes, search) {
+        redacted.push_str(&text[copied..value_start]);
+        redacted.push_str(REDACTED);
+        copied = value_end;
+        search = value_end.max(key_end);
+    }
+
+    redacted.push_str(&text[copied..]);
+    redacted
+}
+
+/// Find the next secret value at or after `from`: the end of its key and the
+/// byte range of its value
+fn next_secret(lower: &str, bytes: &[u8], from: usize) -> Option<(usize, usize, usize)> {
+    let mut from = from;
+    loop {
+        let (start, key) = SECRET_KEYS
+            .iter()
+            .filter_map(|key| lower[from..].find(key).map(|index| (from + index, *key)))
+            .min_by_key(|(index, _)| *index)?;
+        let key_end = start + key.len();
+
+        // Allow a closing quote on the key and spaces, then require a separator
+        let mut index = key_end;
+        while index < bytes.len() && matches!(bytes[index], b'"' | b'\'' | b' ' | b'\t') {
+            index += 1;
+        }
+        if index < bytes.len() && matches!(bytes[index], b'=' | b':') {
+            index += 1;
+            while index < bytes.len() && matches!(bytes[index], b' ' | b'\t') {
+                index += 1;
+            }
+            if let Some((value_start, value_end)) = value_range(lower, bytes, index) {
+                return Some((key_end, value_start, value_end));
+            }
+        }
+        from = key_end;
+    }
+}
+
+/// The value starting at `start`: up to the closing quote if quoted, otherwise
+/// up to the next delimiter. Covers both words of `Bearer <token>`.
+fn value_range(lower: &str, bytes: &[u8], start: usize) -> Option<(usize, usize)> {
+    let quote = bytes.get(start).filter(|byte| matches!(byte, b'"' | b'\''));
+    if let Some(&quote) = quote {
+        let len = bytes[start + 1..].iter().position(|&byte| byte == quote)?;
+        return (len > 0).then_some((start + 1, start + 1 + len));
+    }
+
+    let mut value_start = start;
+    for scheme in ["bearer ", "basic "] {
+        if lower[start..].starts_with(scheme) {
+            value_start += scheme.len();
+        }
+    }
+    let len = bytes[value_start..]
+        .iter()
+        .position(|byte| byte.is_ascii_whitespace() || matches!(byte, b'&' | b',' | b';' | b'"' | b'\'' | b'}'))
+        .unwrap_or(bytes.len() - value_start);
+    (len > 0).then_some((value_start, value_start + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_json_values() {
+        assert_eq!(
+            redact(r#"{"pairingToken": "abc123", "port": 5555}"#),
+            r#"{"pairingToken": "[redacted]", "port": 5555}"#
+        );
+        assert_eq!(redact(r#"{"password":"hunter2"}"#), r#"{"password":"[redacted]"}"#);
+    }
+
+    #[test]
+    fn redacts_query_strings() {
+        assert_eq!(
+            redact("GET /ws?token=abc123&x=1 HTTP/1.1"),
+            "GET /ws?token=[redacted]&x=1 HTTP/1.1"
+        );
+        assert_eq!(redact("api_key=k1;secret=s2"), "api_key=[redacted];secret=[redacted]");
+    }
+
+    #[test]
+    fn redacts_both_words_of_authorization_headers() {
+        assert_eq!(redact("Authorization: Bearer eyJhbGciOi.x.y"), "Authorization: Bearer [redacted]");
+        assert_eq!(redact("authorization: Basic dXNlcjpwYXNz\nnext"), "authorization: Basic [redacted]\nnext");
+    }
+
+    #[test]
+    fn redacts_single_quoted_values() {
+        assert_eq!(redact("{'token': 'abc 123'}"), "{'token': '[redacted]'}");
+        assert_eq!(redact("secret = 'x'"), "secret = '[redacted]'");
+    }
+
+    #[test]
+    fn leaves_empty_values() {
+        for text in ["token=", "token=&x=1", r#""token": """#, "password: ", "token: ''"] {
+            assert_eq!(redact(text), text);
+        }
+    }
+
+    #[test]
+    fn leaves_keys_that_only_contain_a_secret_word() {
+        for text in ["tokenizer: bpe", "secrets folder", "password reset sent", "token"] {
+            assert_eq!(redact(text), text);
+        }
+        assert_eq!(redact("tokenizer: bpe, token: abc"), "tokenizer: bpe, token: [redacted]");
+    }
+
+    #[test]
+    fn keeps_multibyte_text_around_secrets() {
+        assert_eq!(redact("café token=ünïcødé&naïve=1"), "café token=[redacted]&naïve=1");
+        assert_eq!(redact("🎹password:🎛️ ok"), "🎹password:[redacted] ok");
+        assert_eq!(redact("\"Tökenß\": \"x\" token=é"), "\"Tökenß\": \"x\" token=[redacted]");
+        assert_eq!(redact("TOKEN=ÄÖÜ"), "TOKEN=[redacted]");
+        // Every split point of a multibyte string, to show slicing can't panic
+        let text = "ü token=ö & secret: '日本' ß";
+        for end in (0..=text.len()).filter(|&end| text.is_char_boundary(end)) {
+            redact(&text[..end]);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! `--headless`: serve the UI without the tray or any windows
+//!
+//! For a server on the LAN, e.g. a Linux box serving the UI while Live runs on
+//! another machine. Restarts the bridge if it exits, logs Ableton's connection,
+//! advertises the UI over mDNS, and shuts down cleanly on SIGTERM or Ctrl+C.
+
+use std::time::Duration;
+
+use log::LevelFilter;
+
+use crate::bridge;
+use crate::mdns::Advertiser;
+use crate::settings::{BridgeSettings, Settings};
+
+/// Matches the bridge's own connection check interval
+const MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A bridge that ran this long was healthy, so restart it straight away
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(30);
+
+/// Run until signalled and return the process exit code
+pub fn run(settings: &Settings, ui_port: u16, log_level: LevelFilter) -> i32 {
+    let settings = settings.clone();
+    tauri::async_runtime::block_on(async move {
+        log::info!("[headless] Serving on port {}, Ableton on {}", ui_port, settings.bridge.osc_host);
+
+        // Keep advertising until we return
+        let _advertiser = Advertiser::start(ui_port)
+            .map_err(|e| log::warn!("[mdns] {}", e))
+            .ok();
+        tauri::async_runtime::spawn(monitor(ui_port, settings.bridge.osc_host.clone()));
+
+        tokio::select! {
+            signal = shutdown_signal() => {
+                // Dropping the supervisor kills the bridge
+                log::info!("[headless] Received {}, shutting down", signal);
+                crate::cli::EXIT_OK
+            }
+            Err(e) = supervise(settings.bridge.clone(), log_level) => {
+                log::error!("[headless] {}", e);
+                crate::cli::EXIT_FAILURE
+            }
+        }
+    })
+}
+
+/// Start the bridge and restart it whenever it exits, backing off if it keeps
+/// failing. Only returns if the bridge can't be started at all.
+#[cfg(not(debug_assertions))]
+async fn supervise(bridge: BridgeSettings, log_level: LevelFilter) -> Result<(), String> {
+    use std::process::Stdio;
+    use tokio::process::Command;
+
+    let program = sidecar_path(&bridge.sidecar)?;
+    let mut backoff = Backoff::default();
+
+    loop {
+        let started = std::time::Instant::now();
+        let mut child = Command::new(&program)
+            .args(bridge::args(&bridge, &bridge::OscRoute::direct(&bridge.osc_host), log_level))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Could not start bridge {:?}: {}", program, e))?;
+        log::info!("Bridge started on port {}", bridge.ui_port);
+
+        forward_output(child.stdout.take(), log::Level::Info);
+        forward_output(child.stderr.take(), log::Level::Warn);
+
+        let status = child
+            .wait()
+            .await
+            .map_err(|e| format!("Could not wait for bridge: {}", e))?;
+
+        let delay = backoff.after(started.elapsed());
+        log::error!("[headless] Bridge exited ({}), restarting in {}s", status, delay.as_secs());
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// How long to wait before restarting the bridge: doubling while it keeps
+/// failing, and back to the minimum once it has run healthily
+#[cfg_attr(debug_assertions, allow(dead_code))]
+struct Backoff {
+    delay: Duration,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff { delay: MIN_RESTART_DELAY }
+    }
+}
+
+#[cfg_attr(debug_assertions, allow(dead_code))]
+impl Backoff {
+    /// The delay before restarting a bridge that exited after running for `ran_for`
+    fn after(&mut self, ran_for: Duration) -> Duration {
+        if ran_for >= HEALTHY_RUN {
+            self.delay = MIN_RESTART_DELAY;
+        }
+        let delay = self.delay;
+        self.delay = (delay * 2).min(MAX_RESTART_DELAY);
+        delay
+    }
+}
+
+#[cfg(debug_assertions)]
+async fn supervise(_bridge: BridgeSettings, _log_level: LevelFilter) -> Result<(), String> {
+    log::info!("[dev] Skipping sidecar - run `bun run server/standalone.ts` manually");
+    std::future::pending().await
+}
+
+/// Sidecars are installed next to the main executable, without the target triple
+/// (where `tauri_plugin_shell` looks for them too)
+#[cfg(not(debug_assertions))]
+fn sidecar_path(name: &str) -> Result<std::path::PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Could not find executable: {}", e))?;
+    let dir = exe.parent().ok_or("Could not find executable directory")?;
+
+    let mut path = dir.join(name);
+    if cfg!(windows) {
+        path.set_extension("exe");
+    }
+    Ok(path)
+}
+
+/// Log each line the bridge prints (it already prefixes them with `[bridge]` etc.)
+#[cfg(not(debug_assertions))]
+fn forward_output<R>(output: Option<R>, level: log::Level)
+where
+    R: tokio::io::AsyncRead + Unpin + Send + 'static,
+{
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let Some(output) = output else { return };
+    tauri::async_runtime::spawn(async move {
+        let mut lines = BufReader::new(output).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            log::log!(level, "{}", line);
+        }
+    });
+}
+
+/// Log when the bridge, Ableton or the number of connected devices changes
+async fn monitor(ui_port: u16, osc_host: String) {
+    let mut last: Option<bridge::BridgeStatus> = None;
+    let mut first = true;
+
+    loop {
+        let status = tauri::async_runtime::spawn_blocking(move || bridge::status(ui_port, Duration::from_secs(2)).ok())
+            .await
+            .unwrap_or_default();
+
+        match (&last, &status) {
+            (Some(_), None) => log::warn!("[headless] Bridge stopped responding on port {}", ui_port),
+            (None, None) if first => log::warn!("[headless] Bridge not responding on port {}", ui_port),
+            (None, None) => {}
+            (None, Some(current)) => {
+                log::info!("[headless] Bridge responding on port {}", ui_port);
+                log_ableton(current.ableton_connected, &osc_host);
+                log_clients(current.clients);
+            }
+            (Some(previous), Some(current)) => {
+                if previous.ableton_connected != current.ableton_connected {
+                    log_ableton(current.ableton_connected, &osc_host);
+                }
+                if previous.clients != current.clients {
+                    log_clients(current.clients);
+                }
+            }
+        }
+
+        first = false;
+        last = status;
+        tokio::time::sleep(MONITOR_INTERVAL).await;
+    }
+}
+
+fn log_ableton(connected: bool, osc_host: &str) {
+    if connected {
+        log::info!("[ableton] Ableton Live connected on {}", osc_host);
+    } else {
+        log::warn!("[ableton] Ableton Live not connected on {}", osc_host);
+    }
+}
+
+fn log_clients(clients: u32) {
+    log::info!("[headless] {} device{} connected", clients, if clients == 1 { "" } else { "s" });
+}
+
+/// Wait for SIGTERM (systemd stopping us) or Ctrl+C, returning its name
+async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => tokio::select! {
+                _ = sigterm.recv() => return "SIGTERM",
+                _ = tokio::signal::ctrl_c() => return "SIGINT",
+            },
+            Err(e) => log::warn!("[headless] Could not listen for SIGTERM: {}", e),
+        }
+    }
+
+    let _ = tokio::signal::ctrl_c().await;
+    "Ctrl+C"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_while_the_bridge_keeps_failing() {
+        let mut backoff = Backoff::default();
+        let delays: Vec<u64> = (0..7).map(|_| backoff.after(Duration::from_secs(2)).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+    }
+
+    #[test]
+    fn restarts_straight_away_after_a_healthy_run() {
+        let mut backoff = Backoff::default();
+        for _ in 0..4 {
+            backoff.after(Duration::ZERO);
+        }
+        assert_eq!(backoff.after(HEALTHY_RUN), MIN_RESTART_DELAY);
+        assert_eq!(backoff.after(HEALTHY_RUN - Duration::from_millis(1)), Duration::from_secs(2));
+        assert_eq!(backoff.after(Duration::from_secs(3600)), MIN_RESTART_DELAY);
+    }
+}
@@ -0,0 +1,138 @@
+//! Global shortcuts for transport and scenes
+//!
+//! Browser shortcuts don't work while Live is focused, and Live's don't work
+//! while the browser is, so these are registered with the OS instead.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use mission_control_protocol::{ClientMessage, Snapshot};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut as Keys, ShortcutState};
+
+use crate::bridge_client;
+use crate::settings::{FieldError, Shortcut, ShortcutAction};
+
+/// Where Next Scene and Previous Scene go from
+#[derive(Default)]
+pub struct Hotkeys {
+    /// Index of the scene last fired by a shortcut
+    scene: Mutex<Option<usize>>,
+}
+
+/// Check that every shortcut parses and none is used twice. Errors are for
+/// `shortcuts.<index>.keys`.
+pub fn validate(shortcuts: &[Shortcut]) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let mut seen = HashMap::new();
+
+    for (index, shortcut) in shortcuts.iter().enumerate() {
+        let error = |message: String| FieldError {
+            field: format!("shortcuts.{}.keys", index).into(),
+            message,
+        };
+        if shortcut.keys.trim().is_empty() {
+            errors.push(error("Press the keys for this shortcut.".to_string()));
+            continue;
+        }
+        let keys = match shortcut.keys.parse::<Keys>() {
+            Ok(keys) => keys,
+            Err(e) => {
+                errors.push(error(format!("Not a shortcut: {}", e)));
+                continue;
+            }
+        };
+        if let ShortcutAction::FireScene { scene: 0 } = shortcut.action {
+            errors.push(error("Scenes are numbered from 1.".to_string()));
+        }
+        // Compared parsed, as Ctrl+Shift+P and Shift+Ctrl+P are the same keys
+        if let Some(first) = seen.insert(keys, index) {
+            errors.push(error(format!("Already used by shortcut {}.", first + 1)));
+        }
+    }
+    errors
+}
+
+/// Replace any registered shortcuts with `shortcuts`. Returns a message for each
+/// that couldn't be registered: it doesn't parse (e.g. from a hand-edited
+/// settings file), or another app has it.
+pub fn register(app: &AppHandle, shortcuts: &[Shortcut]) -> Vec<String> {
+    let global_shortcut = app.global_shortcut();
+    if let Err(e) = global_shortcut.unregister_all() {
+        log::warn!("[hotkeys] Could not unregister shortcuts: {}", e);
+    }
+
+    let mut failures = Vec::new();
+    for shortcut in shortcuts {
+        let keys = match shortcut.keys.parse::<Keys>() {
+            Ok(keys) => keys,
+            Err(e) => {
+                log::warn!("[hotkeys] Could not parse {}: {}", shortcut.keys, e);
+                failures.push(format!("{} isn't a shortcut ({})", shortcut.keys, e));
+                continue;
+            }
+        };
+        let action = shortcut.action.clone();
+        let result = global_shortcut.on_shortcut(keys, move |app, _, event| {
+            if event.state == ShortcutState::Pressed {
+                run(app, &action);
+            }
+        });
+        match result {
+            Ok(()) => log::info!("[hotkeys] Registered {} for {:?}", shortcut.keys, shortcut.action),
+            Err(e) => {
+                log::warn!("[hotkeys] Could not register {}: {}", shortcut.keys, e);
+                failures.push(format!("{} is in use by another app ({})", shortcut.keys, e));
+            }
+        }
+    }
+    failures
+}
+
+fn run(app: &AppHandle, action: &ShortcutAction) {
+    let messages = bridge_client::with_snapshot(app, |snapshot| messages(app, action, snapshot)).unwrap_or_default();
+    for message in messages {
+        if let Err(e) = bridge_client::send(app, message) {
+            log::warn!("[hotkeys] {}", e);
+            return;
+        }
+    }
+}
+
+/// What `action` sends, given the session
+fn messages(app: &AppHandle, action: &ShortcutAction, snapshot: &Snapshot) -> Vec<ClientMessage> {
+    let Some(session) = snapshot.live_session() else {
+        log::warn!("[hotkeys] No session from Live yet");
+        return Vec::new();
+    };
+
+    let scene = match action {
+        ShortcutAction::PlayStop if session.is_playing => return vec![ClientMessage::TransportStop],
+        ShortcutAction::PlayStop => return vec![ClientMessage::TransportPlay],
+        ShortcutAction::TapTempo => return vec![ClientMessage::TransportTapTempo],
+        // Same as the UI's Stop All
+        ShortcutAction::StopAll => {
+            return session.tracks.iter().map(|track| ClientMessage::TrackStop { track_id: track.id }).collect();
+        }
+        ShortcutAction::FireScene { scene } => (*scene as usize).checked_sub(1),
+        ShortcutAction::NextScene | ShortcutAction::PreviousScene => {
+            let last = app.state::<Hotkeys>().scene.lock().unwrap().or_else(|| usize::try_from(session.selected_scene).ok());
+            match (action, last) {
+                (ShortcutAction::NextScene, Some(last)) => Some((last + 1).min(session.scenes.len().saturating_sub(1))),
+                (ShortcutAction::PreviousScene, Some(last)) => Some(last.saturating_sub(1)),
+                _ => Some(0),
+            }
+        }
+    };
+
+    match scene.and_then(|index| Some((index, session.scenes.get(index)?))) {
+        Some((index, scene)) => {
+            *app.state::<Hotkeys>().scene.lock().unwrap() = Some(index);
+            vec![ClientMessage::SceneFire { scene_id: scene.id }]
+        }
+        None => {
+            log::warn!("[hotkeys] No such scene for {:?}", action);
+            Vec::new()
+        }
+    }
+}
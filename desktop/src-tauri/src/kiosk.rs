@@ -0,0 +1,217 @@
+//! Kiosk mode: Mission Control filling a dedicated (touch)screen
+//!
+//! The window is undecorated and full screen on the chosen monitor. It can't be
+//! closed or navigated away from the bridge, and a watchdog puts it back if it
+//! leaves full screen or its monitor, or is destroyed anyway. Turn Kiosk Mode off
+//! or quit from the tray to get out.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tauri::{
+    webview::{NewWindowResponse, WebviewWindowBuilder},
+    AppHandle, Manager, Monitor, Url, WebviewUrl, WebviewWindow, WindowEvent,
+};
+
+pub const LABEL: &str = "kiosk";
+
+/// How often the watchdog checks the window is still in place
+const WATCH_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Delay before reopening a destroyed window, so quitting isn't raced
+const REOPEN_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Default)]
+pub struct Kiosk {
+    active: AtomicBool,
+    watching: AtomicBool,
+    target: Mutex<Target>,
+}
+
+#[derive(Default, Clone)]
+struct Target {
+    url: String,
+    /// Monitor name or 1-based number; the primary monitor if `None`
+    monitor: Option<String>,
+}
+
+/// Whether kiosk mode is on
+pub fn is_active(app: &AppHandle) -> bool {
+    app.state::<Kiosk>().active.load(Ordering::SeqCst)
+}
+
+/// Show `url` in kiosk mode on `monitor`, or move the kiosk there if it's
+/// already running
+pub fn start(app: &AppHandle, url: &str, monitor: Option<String>) -> Result<(), String> {
+    let kiosk = app.state::<Kiosk>();
+    *kiosk.target.lock().unwrap() = Target { url: url.to_string(), monitor };
+
+    if let Some(window) = app.get_webview_window(LABEL) {
+        return place(app, &window);
+    }
+
+    kiosk.active.store(true, Ordering::SeqCst);
+    open_window(app)?;
+    if !kiosk.watching.swap(true, Ordering::SeqCst) {
+        watch(app);
+    }
+    Ok(())
+}
+
+/// Leave kiosk mode and close the window
+pub fn stop(app: &AppHandle) {
+    app.state::<Kiosk>().active.store(false, Ordering::SeqCst);
+    if let Some(window) = app.get_webview_window(LABEL) {
+        let _ = window.destroy();
+    }
+    log::info!("[kiosk] Stopped");
+}
+
+/// Stop recovering the window, e.g. because the app is quitting
+pub fn release(app: &AppHandle) {
+    app.state::<Kiosk>().active.store(false, Ordering::SeqCst);
+}
+
+/// Names of the connected monitors, for choosing one in Settings
+pub fn monitor_names(app: &AppHandle) -> Vec<String> {
+    app.available_monitors()
+        .unwrap_or_default()
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| monitor_name(index, monitor))
+        .collect()
+}
+
+fn monitor_name(index: usize, monitor: &Monitor) -> String {
+    monitor.name().cloned().unwrap_or_else(|| format!("Monitor {}", index + 1))
+}
+
+fn open_window(app: &AppHandle) -> Result<WebviewWindow, String> {
+    let target = app.state::<Kiosk>().target.lock().unwrap().clone();
+    let url: Url = target.url.parse().map_err(|e| format!("Invalid UI URL: {}", e))?;
+    let origin = url.origin();
+
+    let window = WebviewWindowBuilder::new(app, LABEL, WebviewUrl::External(url))
+        .title("Mission Control")
+        .decorations(false)
+        .visible(false)
+        .on_navigation(move |url| {
+            if url.origin() == origin {
+                return true;
+            }
+            log::warn!("[kiosk] Blocked navigation to {}", url);
+            false
+        })
+        .on_new_window(|url, _| {
+            log::warn!("[kiosk] Blocked new window for {}", url);
+            NewWindowResponse::Deny
+        })
+        .build()
+        .map_err(|e| format!("Failed to create kiosk window: {}", e))?;
+
+    // An accessory app can't take over a screen, so be a regular app meanwhile
+    #[cfg(target_os = "macos")]
+    let _ = app.set_activation_policy(tauri::ActivationPolicy::Regular);
+
+    let handle = app.clone();
+    window.on_window_event(move |event| match event {
+        WindowEvent::CloseRequested { api, .. } => {
+            if is_active(&handle) {
+                api.prevent_close();
+            }
+        }
+        WindowEvent::Destroyed => {
+            if is_active(&handle) {
+                log::warn!("[kiosk] Window was destroyed, reopening");
+                reopen_later(&handle);
+            } else {
+                #[cfg(target_os = "macos")]
+                let _ = handle.set_activation_policy(tauri::ActivationPolicy::Accessory);
+            }
+        }
+        _ => {}
+    });
+
+    place(app, &window)?;
+    let _ = window.show();
+    log::info!("[kiosk] Started");
+    Ok(window)
+}
+
+fn reopen_later(app: &AppHandle) {
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(REOPEN_DELAY);
+        if is_active(&handle) && handle.get_webview_window(LABEL).is_none() {
+            if let Err(e) = open_window(&handle) {
+                log::error!("[kiosk] {}", e);
+            }
+        }
+    });
+}
+
+/// Check on the window until kiosk mode is turned off
+fn watch(app: &AppHandle) {
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        while is_active(&handle) {
+            std::thread::sleep(WATCH_INTERVAL);
+            if let Some(window) = handle.get_webview_window(LABEL) {
+                if window.is_minimized().unwrap_or(false) {
+                    let _ = window.unminimize();
+                }
+                if !window.is_visible().unwrap_or(true) {
+                    let _ = window.show();
+                }
+                if let Err(e) = place(&handle, &window) {
+                    log::error!("[kiosk] {}", e);
+                }
+            }
+        }
+        handle.state::<Kiosk>().watching.store(false, Ordering::SeqCst);
+    });
+}
+
+/// Make the window full screen on the chosen monitor, if it isn't already
+fn place(app: &AppHandle, window: &WebviewWindow) -> Result<(), String> {
+    let preferred = app.state::<Kiosk>().target.lock().unwrap().monitor.clone();
+    let Some(monitor) = find_monitor(app, preferred.as_deref()) else {
+        return Ok(());
+    };
+
+    let on_monitor = window
+        .current_monitor()
+        .ok()
+        .flatten()
+        .is_some_and(|current| current.position() == monitor.position());
+    if on_monitor && window.is_fullscreen().unwrap_or(false) {
+        return Ok(());
+    }
+
+    // Leave full screen before moving, or the window stays where it was
+    let place_error = |e: tauri::Error| format!("Could not place kiosk window: {}", e);
+    window.set_fullscreen(false).map_err(place_error)?;
+    window.set_position(*monitor.position()).map_err(place_error)?;
+    window.set_size(*monitor.size()).map_err(place_error)?;
+    window.set_fullscreen(true).map_err(place_error)
+}
+
+/// The monitor named (or numbered, from 1) `preferred`, falling back to the
+/// primary monitor if it isn't connected
+fn find_monitor(app: &AppHandle, preferred: Option<&str>) -> Option<Monitor> {
+    let monitors = app.available_monitors().unwrap_or_default();
+
+    let found = preferred.and_then(|preferred| {
+        if let Ok(number) = preferred.parse::<usize>() {
+            return number.checked_sub(1).and_then(|index| monitors.get(index).cloned());
+        }
+        monitors
+            .iter()
+            .enumerate()
+            .find(|(index, monitor)| monitor_name(*index, monitor).eq_ignore_ascii_case(preferred))
+            .map(|(_, monitor)| monitor.clone())
+    });
+
+    found.or_else(|| app.primary_monitor().ok().flatten()).or_else(|| monitors.into_iter().next())
+}
@@ -0,0 +1,225 @@
+//! Ableton Link peer, for showing the shared tempo, beat phase and peer count
+//!
+//! Joins the Link session on the local network itself, so it works without the
+//! bridge or AbletonOSC. It only reads the session and never changes it.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rusty_link::{AblLink, SessionState};
+use serde::Serialize;
+use tauri::menu::{CheckMenuItem, IsMenuItem, MenuItem};
+use tauri::{AppHandle, Emitter, Manager, Wry};
+
+use crate::commands;
+
+/// Beats per bar for the phase, as in Live by default
+pub const QUANTUM: f64 = 4.0;
+
+/// Tempo Link starts with. It only matters if Mission Control is the first
+/// Link app: apps that join later take its tempo.
+const INITIAL_TEMPO: f64 = 120.0;
+
+/// How often the tray's beat indicator is refreshed
+const TICK: Duration = Duration::from_millis(50);
+
+pub struct Link {
+    peer: AblLink,
+    status: MenuItem<Wry>,
+    toggle: CheckMenuItem<Wry>,
+    /// Last status text in the tray, and last status emitted without the beat
+    shown: Mutex<(String, Option<LinkStatus>)>,
+}
+
+/// What Link reports, from [`status`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkStatus {
+    pub enabled: bool,
+    /// Other Link apps in the session
+    pub peers: u64,
+    pub tempo: f64,
+    /// Beats since the session's timeline started, when read
+    pub beat: f64,
+    /// Position in the bar, from 0 up to `quantum`
+    pub phase: f64,
+    pub quantum: f64,
+    /// Playing, for apps that share start and stop
+    pub playing: bool,
+}
+
+impl Link {
+    pub fn new(app: &AppHandle, enabled: bool) -> tauri::Result<Link> {
+        let peer = AblLink::new(INITIAL_TEMPO);
+        peer.enable_start_stop_sync(true);
+        peer.enable(enabled);
+
+        Ok(Link {
+            peer,
+            status: MenuItem::with_id(app, "link_status", status_text(None), false, None::<&str>)?,
+            toggle: CheckMenuItem::with_id(app, "link_toggle", "Ableton Link", true, enabled, None::<&str>)?,
+            shown: Mutex::new((String::new(), None)),
+        })
+    }
+
+    /// The items, in menu order
+    pub fn items(&self) -> [&dyn IsMenuItem<Wry>; 2] {
+        [&self.status, &self.toggle]
+    }
+
+    fn read(&self) -> LinkStatus {
+        let mut session = SessionState::new();
+        self.peer.capture_app_session_state(&mut session);
+        let now = self.peer.clock_micros();
+
+        LinkStatus {
+            enabled: self.peer.is_enabled(),
+            peers: self.peer.num_peers(),
+            tempo: session.tempo(),
+            beat: session.beat_at_time(now, QUANTUM),
+            phase: session.phase_at_time(now, QUANTUM),
+            quantum: QUANTUM,
+            playing: session.is_playing(),
+        }
+    }
+}
+
+/// e.g. "Link: 120.00 BPM ● ○ ○ ○ 2 peers", with the current beat filled in
+fn status_text(status: Option<&LinkStatus>) -> String {
+    let Some(status) = status.filter(|status| status.enabled) else {
+        return "Link: Off".to_string();
+    };
+    let beat = status.phase.floor() as usize;
+    let beats: Vec<_> = (0..status.quantum as usize).map(|index| if index == beat { "●" } else { "○" }).collect();
+    let peers = match status.peers {
+        0 => "no peers".to_string(),
+        1 => "1 peer".to_string(),
+        peers => format!("{} peers", peers),
+    };
+    format!("Link: {:.2} BPM {} {}", status.tempo, beats.join(" "), peers)
+}
+
+/// Keep the tray and webviews up to date with Link, for as long as the app runs
+pub fn start(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            update(&app);
+            tokio::time::sleep(TICK).await;
+        }
+    });
+}
+
+fn update(app: &AppHandle) {
+    let Some(link) = app.try_state::<Link>() else {
+        return;
+    };
+    let status = link.read();
+    let text = status_text(Some(&status));
+    // Only what's worth telling webviews about
+    let summary = LinkStatus { beat: 0.0, phase: 0.0, ..status.clone() };
+
+    let (text_changed, last) = {
+        // Not held while updating, as menu changes wait for the main thread
+        let mut shown = link.shown.lock().unwrap();
+        let text_changed = shown.0 != text;
+        let last = std::mem::replace(&mut *shown, (text.clone(), Some(summary.clone()))).1;
+        (text_changed, last)
+    };
+
+    if text_changed {
+        if let Err(e) = link.status.set_text(text) {
+            log::error!("Failed to update tray menu: {}", e);
+        }
+    }
+    if last.as_ref() == Some(&summary) {
+        return;
+    }
+    // Not every tempo change, which come in bursts while it's dragged
+    if last.is_none_or(|last| last.enabled != status.enabled || last.peers != status.peers) {
+        log::info!("[link] {} with {} peer(s)", if status.enabled { "On" } else { "Off" }, status.peers);
+    }
+    if let Err(e) = app.emit(commands::LINK_CHANGED, &status) {
+        log::error!("Failed to emit Link status: {}", e);
+    }
+}
+
+/// Join or leave the Link session
+pub fn set_enabled(app: &AppHandle, enabled: bool) {
+    let link = app.state::<Link>();
+    link.peer.enable(enabled);
+    let _ = link.toggle.set_checked(enabled);
+}
+
+/// Handle a click on Ableton Link. Returns `false` if `id` isn't it.
+pub fn handle_menu_event(app: &AppHandle, id: &str) -> bool {
+    if id != "link_toggle" {
+        return false;
+    }
+    let enabled = !app.state::<Link>().peer.is_enabled();
+    crate::update_settings(app, |settings| settings.ableton.link = enabled);
+    set_enabled(app, enabled);
+    true
+}
+
+/// What Link reports right now
+pub fn status(app: &AppHandle) -> LinkStatus {
+    app.state::<Link>().read()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn status(peers: u64, phase: f64) -> LinkStatus {
+        LinkStatus {
+            enabled: true,
+            peers,
+            tempo: 128.5,
+            beat: 16.0 + phase,
+            phase,
+            quantum: QUANTUM,
+            playing: false,
+        }
+    }
+
+    #[test]
+    fn shows_off_when_disabled() {
+        assert_eq!(status_text(None), "Link: Off");
+        assert_eq!(status_text(Some(&LinkStatus { enabled: false, ..status(2, 0.0) })), "Link: Off");
+    }
+
+    #[test]
+    fn shows_tempo_beat_and_peers() {
+        assert_eq!(status_text(Some(&status(0, 0.0))), "Link: 128.50 BPM ● ○ ○ ○ no peers");
+        assert_eq!(status_text(Some(&status(1, 2.75))), "Link: 128.50 BPM ○ ○ ● ○ 1 peer");
+        assert_eq!(status_text(Some(&status(3, 3.99))), "Link: 128.50 BPM ○ ○ ○ ● 3 peers");
+    }
+
+    fn tempo(link: &AblLink) -> f64 {
+        let mut session = SessionState::new();
+        link.capture_app_session_state(&mut session);
+        session.tempo()
+    }
+
+    /// Two peers in one process find each other over multicast on loopback
+    #[test]
+    #[ignore = "needs multicast on the local network"]
+    fn peers_share_a_tempo() {
+        let first = AblLink::new(120.0);
+        let second = AblLink::new(90.0);
+        first.enable(true);
+        second.enable(true);
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !(first.num_peers() == 1 && second.num_peers() == 1 && tempo(&first) == tempo(&second)) {
+            assert!(Instant::now() < deadline, "peers: {} and {}", first.num_peers(), second.num_peers());
+            std::thread::sleep(TICK);
+        }
+        assert!([120.0, 90.0].contains(&tempo(&first)));
+
+        second.enable(false);
+        first.enable(false);
+    }
+}
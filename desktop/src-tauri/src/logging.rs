@@ -0,0 +1,77 @@
+//! Console logging through the `log` facade, filtered by `--log-level`
+//!
+//! The most recent lines are also kept in memory for diagnostics bundles.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// How many lines [`recent`] keeps
+const RECENT_LINES: usize = 2000;
+
+struct ConsoleLogger {
+    recent: Mutex<VecDeque<String>>,
+}
+
+static LOGGER: ConsoleLogger = ConsoleLogger {
+    recent: Mutex::new(VecDeque::new()),
+};
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        if metadata.level() > log::max_level() {
+            return false;
+        }
+        // Dependencies (tauri, wry, ...) are noisy, so only show their warnings
+        // unless tracing everything
+        metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+            || metadata.level() <= Level::Warn
+            || log::max_level() == LevelFilter::Trace
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        match record.level() {
+            Level::Error | Level::Warn => eprintln!("{}", record.args()),
+            _ => println!("{}", record.args()),
+        }
+
+        let line = format!("{} {:<5} {}", timestamp(), record.level(), record.args());
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == RECENT_LINES {
+            recent.pop_front();
+        }
+        recent.push_back(line);
+    }
+
+    fn flush(&self) {}
+}
+
+/// Send log messages at `level` and above to stdout/stderr
+pub fn init(level: LevelFilter) {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// The last lines logged, oldest first, with UTC timestamps and levels
+pub fn recent() -> Vec<String> {
+    LOGGER.recent.lock().unwrap().iter().cloned().collect()
+}
+
+/// Time of day in UTC, e.g. "14:03:27.512Z"
+fn timestamp() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = now.as_secs() % 86_400;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        now.subsec_millis()
+    )
+}
@@ -1,21 +1,56 @@
 // Prevents additional console window on Windows in release
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::fs;
-use std::net::UdpSocket;
+mod ableton;
+mod bridge;
+mod bridge_client;
+mod cli;
+mod commands;
+mod diagnostics;
+mod diagnostics_bundle;
+mod headless;
+mod hotkeys;
+mod kiosk;
+mod link;
+mod logging;
+mod mdns;
+mod midi;
+mod midi_feedback;
+mod midi_mapping;
+mod network;
+#[cfg(not(debug_assertions))]
+mod offline_update;
+mod osc;
+mod osc_capture;
+mod osc_control;
+mod osc_proxy;
+mod osc_relay;
+mod remote_script;
+mod scene_menu;
+mod settings;
+mod settings_window;
+mod transport_menu;
+mod ui_window;
+#[cfg(not(debug_assertions))]
+mod update_window;
+#[cfg_attr(debug_assertions, allow(dead_code))]
+mod updates;
+
 use std::path::PathBuf;
 use std::sync::Mutex;
+use std::time::Duration;
+#[cfg(not(debug_assertions))]
+use std::time::SystemTime;
 use qrcode::QrCode;
 use image::Luma;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use clap::Parser;
 use tauri::{
-    menu::{Menu, MenuItem, PredefinedMenuItem},
+    menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu},
     tray::{TrayIconBuilder, TrayIconEvent},
     webview::WebviewWindowBuilder,
-    AppHandle, Manager, RunEvent,
+    AppHandle, Manager, RunEvent, Wry,
 };
-#[cfg(not(debug_assertions))]
-use tauri::menu::CheckMenuItem;
 use tauri_plugin_autostart::MacosLauncher;
 #[cfg(not(debug_assertions))]
 use tauri_plugin_autostart::ManagerExt;
@@ -24,24 +59,37 @@ use tauri_plugin_dialog::{DialogExt, MessageDialogKind};
 use tauri_plugin_dialog::MessageDialogButtons;
 #[cfg(not(debug_assertions))]
 use tauri_plugin_updater::UpdaterExt;
+use tauri_plugin_window_state::StateFlags;
+use settings::{FieldError, OpenIn, Settings};
+#[cfg(not(debug_assertions))]
+use updates::UpdateChannel;
 #[allow(unused_imports)]
-use tauri_plugin_shell::process::CommandChild;
+use tauri_plugin_shell::process::{CommandChild, CommandEvent};
 #[allow(unused_imports)]
 use tauri_plugin_shell::ShellExt;
 
-// Port depends on dev vs release mode
-#[cfg(debug_assertions)]
-const UI_PORT: u16 = 5173; // Vite dev server
-#[cfg(not(debug_assertions))]
-const UI_PORT: u16 = 5555; // Standalone bridge
+/// Vite dev server port, used instead of the bridge port from settings in dev
+const DEV_UI_PORT: u16 = 5173;
 
-/// Get the local network IP address
-fn get_local_ip() -> Option<String> {
-    // Create a UDP socket and "connect" to a public IP to determine local interface
-    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
-    socket.connect("8.8.8.8:80").ok()?;
-    let addr = socket.local_addr().ok()?;
-    Some(addr.ip().to_string())
+/// Port the UI is served on: Vite's in dev, the standalone bridge's in release
+fn ui_port(app: &AppHandle) -> u16 {
+    ui_port_of(&effective_settings(app))
+}
+
+fn ui_port_of(settings: &Settings) -> u16 {
+    if cfg!(debug_assertions) {
+        DEV_UI_PORT
+    } else {
+        settings.bridge.ui_port
+    }
+}
+
+/// Settings for this launch: the settings file with command-line flags applied
+fn effective_settings(app: &AppHandle) -> Settings {
+    let state = app.state::<AppState>();
+    let mut settings = state.settings.lock().unwrap().clone();
+    state.cli.apply(&mut settings);
+    settings
 }
 
 /// Generate a QR code as base64-encoded PNG
@@ -70,9 +118,68 @@ fn generate_qr_code_base64(data: &str) -> Result<String, String> {
 struct AppState {
     bridge_process: Mutex<Option<CommandChild>>,
     quit_requested: Mutex<bool>,
+    restart_required: Mutex<bool>,
+    ableton_status: Mutex<osc::Probe>,
+    /// As saved in the settings file, without command-line overrides
+    settings: Mutex<Settings>,
+    /// Version found by the last update check
+    update_available: Mutex<Option<String>>,
+    /// Last status sent to webviews
+    last_status: Mutex<Option<commands::AppStatus>>,
+    cli: cli::Cli,
+}
+
+/// Handles to the tray menu for items that change at runtime
+struct TrayMenu {
+    menu: Menu<Wry>,
+    status: MenuItem<Wry>,
+    restart_notice: MenuItem<Wry>,
+    always_on_top: CheckMenuItem<Wry>,
+    fullscreen: CheckMenuItem<Wry>,
+    kiosk: CheckMenuItem<Wry>,
+    #[cfg(not(debug_assertions))]
+    channel_stable: CheckMenuItem<Wry>,
+    #[cfg(not(debug_assertions))]
+    channel_edge: CheckMenuItem<Wry>,
 }
 
 fn main() {
+    if std::env::args_os().len() > 1 {
+        cli::attach_console();
+    }
+    let cli = cli::Cli::parse();
+    logging::init(cli.log_level);
+
+    let context = tauri::generate_context!();
+    let settings = match Settings::path(&context.config().identifier) {
+        Ok(path) => Settings::load(&path),
+        Err(e) => {
+            log::error!("[settings] {}", e);
+            Settings::default()
+        }
+    };
+
+    let mut launch_settings = settings.clone();
+    cli.apply(&mut launch_settings);
+    let flag_errors = cli.validate(&launch_settings);
+    if !flag_errors.is_empty() {
+        for error in flag_errors {
+            log::error!("{}", error);
+        }
+        std::process::exit(cli::EXIT_USAGE);
+    }
+
+    // Subcommands run without the tray
+    if let Some(command) = &cli.command {
+        let resource_dir = tauri::utils::platform::resource_dir(context.package_info(), &tauri::Env::default())
+            .map_err(|e| format!("Could not get resource dir: {}", e));
+        std::process::exit(cli::run(command, &launch_settings, ui_port_of(&launch_settings), resource_dir));
+    }
+
+    if cli.headless {
+        std::process::exit(headless::run(&launch_settings, ui_port_of(&launch_settings), cli.log_level));
+    }
+
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
@@ -81,40 +188,96 @@ fn main() {
             Some(vec![]),
         ))
         .plugin(tauri_plugin_updater::Builder::new().build())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(
+            tauri_plugin_window_state::Builder::new()
+                .with_state_flags(StateFlags::SIZE | StateFlags::POSITION | StateFlags::MAXIMIZED | StateFlags::FULLSCREEN)
+                .with_filter(|label| label == ui_window::LABEL)
+                .build(),
+        )
         .manage(AppState {
             bridge_process: Mutex::new(None),
             quit_requested: Mutex::new(false),
+            restart_required: Mutex::new(false),
+            ableton_status: Mutex::new(osc::Probe::default()),
+            settings: Mutex::new(settings),
+            update_available: Mutex::new(None),
+            last_status: Mutex::new(None),
+            cli,
         })
+        .manage(kiosk::Kiosk::default())
+        .manage(bridge_client::BridgeClient::default())
+        .manage(hotkeys::Hotkeys::default())
+        .manage(midi::Midi::default())
+        .manage(osc_relay::OscRelay::default())
+        .manage(osc_proxy::OscProxy::default())
+        .manage(osc_control::OscControl::default())
+        .invoke_handler(tauri::generate_handler![
+            commands::get_status,
+            commands::get_link_status,
+            commands::get_urls,
+            commands::get_qr_code,
+            commands::install_script,
+            commands::restart_bridge,
+            commands::check_updates,
+        ])
         .setup(|app| {
             // Hide from dock, only show in system tray
             #[cfg(target_os = "macos")]
             app.set_activation_policy(tauri::ActivationPolicy::Accessory);
 
             // Build tray menu
+            let status = MenuItem::with_id(app, "status", ableton_status_text(&osc::Probe::default()), false, None::<&str>)?;
+            let separator0 = PredefinedMenuItem::separator(app)?;
+            let transport = transport_menu::TransportMenu::new(app.handle())?;
+            let [play, record, metronome, loop_, tempo] = transport.items();
+            let scenes = scene_menu::SceneMenu::new(app.handle())?;
+            let link_enabled = app.state::<AppState>().settings.lock().unwrap().ableton.link;
+            let link = link::Link::new(app.handle(), link_enabled)?;
+            let [link_status, link_toggle] = link.items();
+            let separator_transport = PredefinedMenuItem::separator(app)?;
             let help = MenuItem::with_id(app, "help", "Help", true, None::<&str>)?;
             let separator1 = PredefinedMenuItem::separator(app)?;
             let open_ui = MenuItem::with_id(app, "open_ui", "Open Mission Control", true, None::<&str>)?;
             let show_qr = MenuItem::with_id(app, "show_qr", "Connect Another Device", true, None::<&str>)?;
+            let always_on_top_enabled = app.state::<AppState>().settings.lock().unwrap().ui.always_on_top;
+            let always_on_top = CheckMenuItem::with_id(app, "always_on_top", "Always on Top", true, always_on_top_enabled, None::<&str>)?;
+            let fullscreen = CheckMenuItem::with_id(app, "fullscreen", "Full Screen", true, false, None::<&str>)?;
+            let kiosk = CheckMenuItem::with_id(app, "kiosk", "Kiosk Mode", true, false, None::<&str>)?;
+            let window = Submenu::with_items(app, "Window", true, &[&always_on_top, &fullscreen, &kiosk])?;
             let separator2 = PredefinedMenuItem::separator(app)?;
             let install_script = MenuItem::with_id(app, "install_script", "Install AbletonOSC", true, None::<&str>)?;
+            let check_ports = MenuItem::with_id(app, "check_ports", "Check Ports...", true, None::<&str>)?;
+            let diagnostics_bundle = MenuItem::with_id(app, "diagnostics_bundle", "Create Diagnostics Bundle...", true, None::<&str>)?;
+            let settings = MenuItem::with_id(app, "settings", "Settings...", true, None::<&str>)?;
             let donate = MenuItem::with_id(app, "donate", "Donate ❤️", true, None::<&str>)?;
             let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+            let restart_notice = MenuItem::with_id(app, "restart_notice", "⚠️ Restart Ableton Live to load AbletonOSC", false, None::<&str>)?;
 
             // Only show autostart and updates options in release builds
+            #[cfg(not(debug_assertions))]
+            let channel = app.state::<AppState>().settings.lock().unwrap().updates.channel;
+            #[cfg(not(debug_assertions))]
+            let channel_stable = CheckMenuItem::with_id(app, "channel_stable", UpdateChannel::Stable.label(), true, channel == UpdateChannel::Stable, None::<&str>)?;
+            #[cfg(not(debug_assertions))]
+            let channel_edge = CheckMenuItem::with_id(app, "channel_edge", UpdateChannel::Edge.label(), true, channel == UpdateChannel::Edge, None::<&str>)?;
+
             #[cfg(not(debug_assertions))]
             let menu = {
                 let autostart_manager = app.autolaunch();
                 let autostart_enabled = autostart_manager.is_enabled().unwrap_or(false);
                 let autostart = CheckMenuItem::with_id(app, "autostart", "Start Automatically", true, autostart_enabled, None::<&str>)?;
                 let check_updates = MenuItem::with_id(app, "check_updates", "Check for Updates...", true, None::<&str>)?;
+                let install_update_file = MenuItem::with_id(app, "install_update_file", "Install Update from File...", true, None::<&str>)?;
+                let update_channel = Submenu::with_items(app, "Update Channel", true, &[&channel_stable, &channel_edge])?;
                 let separator3 = PredefinedMenuItem::separator(app)?;
-                Menu::with_items(app, &[&help, &separator1, &open_ui, &show_qr, &separator2, &install_script, &autostart, &check_updates, &separator3, &donate, &quit])?
+                Menu::with_items(app, &[&status, &separator0, play, record, metronome, loop_, tempo, scenes.submenu(), link_status, link_toggle, &separator_transport, &help, &separator1, &open_ui, &show_qr, &window, &separator2, &install_script, &check_ports, &diagnostics_bundle, &autostart, &check_updates, &install_update_file, &update_channel, &settings, &separator3, &donate, &quit])?
             };
 
             #[cfg(debug_assertions)]
             let menu = {
                 let separator3 = PredefinedMenuItem::separator(app)?;
-                Menu::with_items(app, &[&help, &separator1, &open_ui, &show_qr, &separator2, &install_script, &separator3, &donate, &quit])?
+                Menu::with_items(app, &[&status, &separator0, play, record, metronome, loop_, tempo, scenes.submenu(), link_status, link_toggle, &separator_transport, &help, &separator1, &open_ui, &show_qr, &window, &separator2, &install_script, &check_ports, &diagnostics_bundle, &settings, &separator3, &donate, &quit])?
             };
 
             // Create tray icon with custom rocket icon
@@ -129,37 +292,101 @@ fn main() {
                 .on_tray_icon_event(|_tray, event| {
                     match event {
                         TrayIconEvent::Click { button, .. } => {
-                            println!("[tray] Click: {:?}", button);
+                            log::info!("[tray] Click: {:?}", button);
                         }
                         _ => {}
                     }
                 })
                 .build(app)?;
 
+            app.manage(TrayMenu {
+                menu,
+                status,
+                restart_notice,
+                always_on_top,
+                fullscreen,
+                kiosk,
+                #[cfg(not(debug_assertions))]
+                channel_stable,
+                #[cfg(not(debug_assertions))]
+                channel_edge,
+            });
+            app.manage(transport);
+            app.manage(scenes);
+            app.manage(link);
+            link::start(app.handle());
+            bridge_client::start(app.handle());
+
+            // The proxy first, as the bridge listens where it passes replies on
+            let launch = effective_settings(app.handle());
+            osc_relay::start(app.handle(), &launch.relay);
+            osc_proxy::start(app.handle(), &launch);
+            osc_control::start(app.handle(), &launch.osc_control);
+
             // Start bridge sidecar (only in release builds)
             // In dev mode, run `bun run server/standalone.ts` separately
             #[cfg(not(debug_assertions))]
             start_bridge(app.handle());
 
             #[cfg(debug_assertions)]
-            println!("[dev] Skipping sidecar - run `bun run server/standalone.ts` manually");
+            {
+                log::info!("[dev] Skipping sidecar - run `bun run server/standalone.ts` manually");
+                let route = osc_proxy::bridge_route(app.handle(), &launch);
+                if route != bridge::OscRoute::direct(&launch.bridge.osc_host) {
+                    log::info!("[dev] Start it with {} to go through the OSC proxy", route.args().join(" "));
+                }
+            }
+
+            monitor_ableton(app.handle());
+
+            // Kiosk mode shows the UI itself
+            let unregistered = hotkeys::register(app.handle(), &launch.shortcuts);
+            if !unregistered.is_empty() {
+                app.dialog()
+                    .message(format!("Some shortcuts don't work: {}.\n\nChange them in Settings.", unregistered.join("; ")))
+                    .title("Shortcuts Not Registered")
+                    .kind(MessageDialogKind::Warning)
+                    .show(|_| {});
+            }
+            midi::start(app.handle(), &launch.midi);
+            if launch.ui.kiosk {
+                when_bridge_ready(app.handle(), start_kiosk);
+            } else if launch.ui.open_on_launch {
+                when_bridge_ready(app.handle(), open_ui);
+            }
+            if app.state::<AppState>().cli.show_qr {
+                show_qr(app.handle());
+            }
 
-            // Check for updates on startup (release only)
+            // Check for updates on startup and periodically after (release only)
             #[cfg(not(debug_assertions))]
             {
                 let handle = app.handle().clone();
                 tauri::async_runtime::spawn(async move {
                     // Small delay to let app fully initialize
                     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-                    if let Err(e) = check_for_updates(handle, false).await {
-                        eprintln!("Update check failed: {}", e);
+                    loop {
+                        let automatic = effective_settings(&handle).updates.automatic;
+                        // Don't wait a full interval to check again after a show
+                        let delay = if !automatic {
+                            updates::CHECK_INTERVAL
+                        } else if show_in_progress(&handle).await {
+                            log::info!("Show in progress, postponing update check");
+                            updates::BUSY_RETRY_INTERVAL
+                        } else {
+                            if let Err(e) = check_for_updates(handle.clone(), false).await {
+                                log::error!("Update check failed: {}", e);
+                            }
+                            updates::CHECK_INTERVAL
+                        };
+                        tokio::time::sleep(delay).await;
                     }
                 });
             }
 
             Ok(())
         })
-        .build(tauri::generate_context!())
+        .build(context)
         .expect("error while building tauri application")
         .run(|app, event| {
             match event {
@@ -170,6 +397,9 @@ fn main() {
                     if !quit_requested {
                         // Only prevent exit for window closes, not deliberate quit
                         api.prevent_exit();
+                    } else {
+                        // Don't bring the kiosk back while its window closes
+                        kiosk::release(app);
                     }
                 }
                 RunEvent::Exit => {
@@ -182,155 +412,71 @@ fn main() {
 }
 
 fn handle_menu_event(app: &AppHandle, id: &str) {
+    if transport_menu::handle_menu_event(app, id) || scene_menu::handle_menu_event(app, id) || link::handle_menu_event(app, id) {
+        return;
+    }
+
     match id {
-        "open_ui" => {
-            let url = format!("http://localhost:{}", UI_PORT);
-            if let Err(e) = open::that(&url) {
-                eprintln!("Failed to open browser: {}", e);
+        "open_ui" => open_ui(app),
+        "show_qr" => show_qr(app),
+        "always_on_top" => {
+            let enabled = !app.state::<AppState>().settings.lock().unwrap().ui.always_on_top;
+            update_settings(app, |settings| settings.ui.always_on_top = enabled);
+            let _ = app.state::<TrayMenu>().always_on_top.set_checked(enabled);
+            if let Some(window) = app.get_webview_window(ui_window::LABEL) {
+                let _ = window.set_always_on_top(enabled);
             }
         }
-        "show_qr" => {
-            // If QR window already exists, focus it
-            if let Some(window) = app.get_webview_window("qr") {
-                let _ = window.set_focus();
-                return;
-            }
-
-            // Generate QR code and show in window
-            let url = if let Some(ip) = get_local_ip() {
-                format!("http://{}:{}", ip, UI_PORT)
+        "kiosk" => {
+            let enabled = !kiosk::is_active(app);
+            update_settings(app, |settings| settings.ui.kiosk = enabled);
+            if enabled {
+                start_kiosk(app);
             } else {
-                format!("http://localhost:{}", UI_PORT)
-            };
-
-            match generate_qr_code_base64(&url) {
-                Ok(base64_png) => {
-                    let html = format!(r#"
-                        <!DOCTYPE html>
-                        <html>
-                        <head>
-                            <style>
-                                * {{
-                                    box-sizing: border-box;
-                                }}
-                                body {{
-                                    margin: 0;
-                                    display: flex;
-                                    flex-direction: column;
-                                    align-items: center;
-                                    justify-content: center;
-                                    height: 100vh;
-                                    background: linear-gradient(145deg, #1a1a1a 0%, #0d0d0d 100%);
-                                    color: #fff;
-                                    font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif;
-                                    padding: 24px;
-                                }}
-                                .card {{
-                                    background: linear-gradient(145deg, #242424 0%, #1a1a1a 100%);
-                                    border: 1px solid #333;
-                                    border-radius: 16px;
-                                    padding: 20px;
-                                    display: flex;
-                                    flex-direction: column;
-                                    align-items: center;
-                                    box-shadow: 0 8px 32px rgba(0, 0, 0, 0.4);
-                                }}
-                                h1 {{
-                                    margin: 0 0 16px 0;
-                                    font-size: 18px;
-                                    font-weight: 600;
-                                    color: #fff;
-                                }}
-                                .qr-container {{
-                                    background: #fff;
-                                    padding: 12px;
-                                    border-radius: 12px;
-                                    box-shadow: 0 4px 16px rgba(0, 0, 0, 0.2);
-                                }}
-                                img {{
-                                    display: block;
-                                    border-radius: 4px;
-                                }}
-                                .url {{
-                                    margin: 16px 0 0 0;
-                                    font-size: 12px;
-                                    color: #f90;
-                                    font-family: 'SF Mono', Menlo, Monaco, monospace;
-                                    background: rgba(255, 153, 0, 0.1);
-                                    padding: 8px 12px;
-                                    border-radius: 6px;
-                                    border: 1px solid rgba(255, 153, 0, 0.2);
-                                }}
-                                .hint {{
-                                    margin: 8px 0 0 0;
-                                    font-size: 11px;
-                                    color: #666;
-                                }}
-                            </style>
-                        </head>
-                        <body>
-                            <div class="card">
-                                <h1>Scan to Connect</h1>
-                                <div class="qr-container">
-                                    <img src="data:image/png;base64,{}" width="180" height="180" />
-                                </div>
-                                <p class="url">{}</p>
-                                <p class="hint">or visit the URL directly</p>
-                            </div>
-                        </body>
-                        </html>
-                    "#, base64_png, url);
-
-                    let data_url = format!("data:text/html,{}", urlencoding::encode(&html));
-                    match data_url.parse() {
-                        Ok(parsed_url) => {
-                            match WebviewWindowBuilder::new(app, "qr", tauri::WebviewUrl::External(parsed_url))
-                                .title("Connect Another Device")
-                                .inner_size(320.0, 400.0)
-                                .resizable(false)
-                                .center()
-                                .build()
-                            {
-                                Ok(_) => {}
-                                Err(e) => {
-                                    eprintln!("Failed to create QR window: {}", e);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            eprintln!("Failed to parse data URL: {}", e);
-                            app.dialog()
-                                .message("Failed to create QR code window")
-                                .title("Error")
-                                .kind(MessageDialogKind::Error)
-                                .blocking_show();
-                        }
-                    }
+                kiosk::stop(app);
+                let _ = app.state::<TrayMenu>().kiosk.set_checked(false);
+            }
+        }
+        "fullscreen" => {
+            // Opens the window first if needed, even when the browser is preferred
+            let result = open_ui_window(app).and_then(|window| ui_window::toggle_fullscreen(&window));
+            let tray = app.state::<TrayMenu>();
+            match result {
+                Ok(fullscreen) => {
+                    let _ = tray.fullscreen.set_checked(fullscreen);
                 }
                 Err(e) => {
-                    eprintln!("Failed to generate QR code: {}", e);
-                    app.dialog()
-                        .message(format!("Failed to generate QR code:\n\n{}", e))
-                        .title("Error")
-                        .kind(MessageDialogKind::Error)
-                        .blocking_show();
+                    log::error!("{}", e);
+                    let _ = tray.fullscreen.set_checked(false);
                 }
             }
         }
         "install_script" => {
             match install_remote_script(app) {
                 Ok(path) => {
+                    // Live only loads remote scripts at startup
+                    let live_running = ableton::is_live_running();
+                    if live_running {
+                        set_restart_required(app, true);
+                    }
+
+                    let next_step = if live_running {
+                        "Ableton Live is running and must be restarted to load the script. Quit and reopen Live, then enable"
+                    } else {
+                        "Start Ableton Live and enable"
+                    };
+
                     app.dialog()
                         .message(format!(
-                            "AbletonOSC installed successfully.\n\nLocation: {}\n\nRestart Ableton Live and enable AbletonOSC in Preferences → Link, Tempo & MIDI → Control Surface.",
-                            path
+                            "AbletonOSC installed successfully.\n\nLocation: {}\n\n{} AbletonOSC in Preferences → Link, Tempo & MIDI → Control Surface.",
+                            path, next_step
                         ))
-                        .title("Installation Complete")
-                        .kind(MessageDialogKind::Info)
+                        .title(if live_running { "Restart Required" } else { "Installation Complete" })
+                        .kind(if live_running { MessageDialogKind::Warning } else { MessageDialogKind::Info })
                         .blocking_show();
                 }
                 Err(e) => {
-                    eprintln!("Failed to install remote script: {}", e);
+                    log::error!("Failed to install remote script: {}", e);
                     app.dialog()
                         .message(format!("Failed to install remote script:\n\n{}", e))
                         .title("Installation Failed")
@@ -339,6 +485,24 @@ fn handle_menu_event(app: &AppHandle, id: &str) {
                 }
             }
         }
+        "check_ports" => {
+            let reports = diagnostics::check_ports(ui_port(app));
+            let has_problems = reports.iter().any(|r| r.problem().is_some());
+            for report in &reports {
+                log::info!("[diagnostics] {}", report.describe());
+            }
+
+            app.dialog()
+                .message(diagnostics::summary(&reports))
+                .title(if has_problems { "Port Problems Found" } else { "Ports OK" })
+                .kind(if has_problems { MessageDialogKind::Warning } else { MessageDialogKind::Info })
+                .blocking_show();
+        }
+        "diagnostics_bundle" => {
+            let handle = app.clone();
+            std::thread::spawn(move || create_diagnostics_bundle(&handle));
+        }
+        "settings" => open_settings(app),
         #[cfg(not(debug_assertions))]
         "autostart" => {
             let autostart_manager = app.autolaunch();
@@ -346,11 +510,11 @@ fn handle_menu_event(app: &AppHandle, id: &str) {
 
             if is_enabled {
                 if let Err(e) = autostart_manager.disable() {
-                    eprintln!("Failed to disable autostart: {}", e);
+                    log::error!("Failed to disable autostart: {}", e);
                 }
             } else {
                 if let Err(e) = autostart_manager.enable() {
-                    eprintln!("Failed to enable autostart: {}", e);
+                    log::error!("Failed to enable autostart: {}", e);
                 }
             }
         }
@@ -359,18 +523,37 @@ fn handle_menu_event(app: &AppHandle, id: &str) {
             let handle = app.clone();
             tauri::async_runtime::spawn(async move {
                 if let Err(e) = check_for_updates(handle, true).await {
-                    eprintln!("Update check failed: {}", e);
+                    log::error!("Update check failed: {}", e);
                 }
             });
         }
+        #[cfg(not(debug_assertions))]
+        "install_update_file" => {
+            let handle = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = install_update_from_file(&handle).await {
+                    log::error!("Offline update failed: {}", e);
+                    handle.dialog()
+                        .message(format!("Failed to install update:\n\n{}", e))
+                        .title("Update Error")
+                        .kind(MessageDialogKind::Error)
+                        .blocking_show();
+                }
+            });
+        }
+        #[cfg(not(debug_assertions))]
+        "channel_stable" | "channel_edge" => {
+            let channel = if id == "channel_edge" { UpdateChannel::Edge } else { UpdateChannel::Stable };
+            set_update_channel(app, channel);
+        }
         "help" => {
             if let Err(e) = open::that("https://github.com/ryangavin/mission-control/blob/main/docs/MANUAL.md") {
-                eprintln!("Failed to open help link: {}", e);
+                log::error!("Failed to open help link: {}", e);
             }
         }
         "donate" => {
             if let Err(e) = open::that("https://ko-fi.com/ryangavin") {
-                eprintln!("Failed to open donate link: {}", e);
+                log::error!("Failed to open donate link: {}", e);
             }
         }
         "quit" => {
@@ -385,101 +568,407 @@ fn handle_menu_event(app: &AppHandle, id: &str) {
     }
 }
 
+fn open_ui(app: &AppHandle) {
+    match effective_settings(app).ui.open_in {
+        OpenIn::Browser => {
+            let url = format!("http://localhost:{}", ui_port(app));
+            if let Err(e) = open::that(&url) {
+                log::error!("Failed to open browser: {}", e);
+            }
+        }
+        OpenIn::Window => {
+            if let Err(e) = open_ui_window(app) {
+                log::error!("{}", e);
+            }
+        }
+    }
+}
+
+/// Open the Mission Control window, keeping the tray's Full Screen item in sync
+fn open_ui_window(app: &AppHandle) -> Result<tauri::WebviewWindow, String> {
+    let settings = effective_settings(app);
+    let url = format!("http://localhost:{}", ui_port_of(&settings));
+    let handle = app.clone();
+
+    ui_window::open(app, &url, settings.ui.always_on_top, move |fullscreen| {
+        let _ = handle.state::<TrayMenu>().fullscreen.set_checked(fullscreen);
+    })
+}
+
+/// Run `show` (which shows the UI) once the bridge answers, giving up waiting
+/// after a few seconds
+fn when_bridge_ready(app: &AppHandle, show: fn(&AppHandle)) {
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        let port = ui_port(&handle);
+        for _ in 0..20 {
+            if cfg!(debug_assertions) || bridge::status(port, std::time::Duration::from_millis(500)).is_ok() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+        show(&handle);
+    });
+}
+
+/// Show the UI in kiosk mode on the monitor from settings
+fn start_kiosk(app: &AppHandle) {
+    let settings = effective_settings(app);
+    let url = format!("http://localhost:{}", ui_port_of(&settings));
+
+    let started = kiosk::start(app, &url, settings.ui.kiosk_monitor);
+    if let Err(e) = &started {
+        log::error!("[kiosk] {}", e);
+    }
+    let _ = app.state::<TrayMenu>().kiosk.set_checked(started.is_ok());
+}
+
+/// Show the Connect Another Device window (connect.html, from the bundled UI)
+fn show_qr(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("connect") {
+        let _ = window.set_focus();
+        return;
+    }
+
+    let result = WebviewWindowBuilder::new(app, "connect", tauri::WebviewUrl::App("connect.html".into()))
+        .title("Connect Another Device")
+        .inner_size(340.0, 480.0)
+        .resizable(false)
+        .center()
+        .build();
+
+    if let Err(e) = result {
+        log::error!("Failed to create connect window: {}", e);
+        app.dialog()
+            .message(format!("Failed to open the Connect Another Device window:\n\n{}", e))
+            .title("Error")
+            .kind(MessageDialogKind::Error)
+            .blocking_show();
+    }
+}
+
+/// Show or hide the "restart Ableton Live" notice at the top of the tray menu
+fn set_restart_required(app: &AppHandle, required: bool) {
+    let state = app.state::<AppState>();
+    let mut restart_required = state.restart_required.lock().unwrap();
+    if *restart_required == required {
+        return;
+    }
+    *restart_required = required;
+
+    let tray = app.state::<TrayMenu>();
+    let result = if required {
+        tray.menu.prepend(&tray.restart_notice)
+    } else {
+        tray.menu.remove(&tray.restart_notice)
+    };
+    if let Err(e) = result {
+        log::error!("Failed to update tray menu: {}", e);
+    }
+}
+
+/// Tray status line for the latest AbletonOSC probe
+fn ableton_status_text(probe: &osc::Probe) -> String {
+    match (probe.responding, &probe.live_version) {
+        (true, Some(version)) => format!("Ableton Live {} connected", version),
+        (true, None) => "Ableton Live connected".to_string(),
+        (false, _) => "Ableton Live not connected".to_string(),
+    }
+}
+
+/// Probe AbletonOSC in the background, keeping the tray status line current and
+/// clearing the restart notice once the freshly installed script answers.
+///
+/// A script that was already loaded keeps answering until Live restarts, so the
+/// notice only clears once the probe has failed (or Live has quit) at least once
+/// since the install.
+fn monitor_ableton(app: &AppHandle) {
+    let handle = app.clone();
+    std::thread::spawn(move || {
+        let mut seen_down = false;
+
+        loop {
+            let state = handle.state::<AppState>();
+            let bridge_running = state.bridge_process.lock().unwrap().is_some();
+            let settings = effective_settings(&handle);
+            let mut probe = ableton::probe_script(ui_port_of(&settings), &settings.bridge.osc_host, bridge_running);
+
+            let changed = {
+                let mut status = state.ableton_status.lock().unwrap();
+                // The bridge can't report the version, so keep the last one we saw
+                if probe.responding && probe.live_version.is_none() {
+                    probe.live_version = status.live_version.clone();
+                }
+                let changed = *status != probe;
+                *status = probe.clone();
+                changed
+            };
+
+            if changed {
+                log::info!("[ableton] {}", ableton_status_text(&probe));
+                let tray = handle.state::<TrayMenu>();
+                if let Err(e) = tray.status.set_text(ableton_status_text(&probe)) {
+                    log::error!("Failed to update tray status: {}", e);
+                }
+            }
+
+            if !*state.restart_required.lock().unwrap() {
+                seen_down = false;
+            } else if !probe.responding || !ableton::is_live_running() {
+                seen_down = true;
+            } else if seen_down {
+                log::info!("[ableton] AbletonOSC is responding, restart complete");
+                set_restart_required(&handle, false);
+            }
+
+            // Also picks up devices connecting and the bridge going down
+            commands::emit_status(&handle);
+
+            // Matches the bridge's own connection check interval
+            std::thread::sleep(Duration::from_secs(5));
+        }
+    });
+}
+
 #[cfg(not(debug_assertions))]
 fn start_bridge(app: &AppHandle) {
     let state = app.state::<AppState>();
     let mut process = state.bridge_process.lock().unwrap();
 
     if process.is_some() {
-        println!("Bridge already running");
+        log::info!("Bridge already running");
         return;
     }
 
+    let settings = effective_settings(app);
+    let bridge = &settings.bridge;
+    let log_level = app.state::<AppState>().cli.log_level;
+
     // Get the sidecar command
     let shell = app.shell();
 
-    match shell.sidecar("mission-control-bridge") {
+    match shell.sidecar(&bridge.sidecar) {
         Ok(cmd) => {
-            match cmd.spawn() {
-                Ok((_, child)) => {
-                    println!("Bridge started on port {}", UI_PORT);
+            match cmd.args(bridge::args(bridge, &osc_proxy::bridge_route(app, &settings), log_level)).spawn() {
+                Ok((mut events, child)) => {
+                    log::info!("Bridge started on port {}", bridge.ui_port);
                     *process = Some(child);
+
+                    // Log each line the bridge prints, so it's in diagnostics
+                    // bundles (it already prefixes them with `[bridge]` etc.)
+                    tauri::async_runtime::spawn(async move {
+                        while let Some(event) = events.recv().await {
+                            match event {
+                                CommandEvent::Stdout(line) => log::info!("{}", String::from_utf8_lossy(&line).trim_end()),
+                                CommandEvent::Stderr(line) => log::warn!("{}", String::from_utf8_lossy(&line).trim_end()),
+                                _ => {}
+                            }
+                        }
+                    });
                 }
                 Err(e) => {
-                    eprintln!("Failed to start bridge: {}", e);
+                    log::error!("Failed to start bridge: {}", e);
                 }
             }
         }
         Err(e) => {
-            eprintln!("Failed to create sidecar command: {}", e);
+            log::error!("Failed to create sidecar command: {}", e);
         }
     }
 }
 
+/// Restart the bridge to pick up changed settings
+fn restart_bridge(app: &AppHandle) {
+    #[cfg(not(debug_assertions))]
+    {
+        stop_bridge(app);
+        start_bridge(app);
+    }
+
+    #[cfg(debug_assertions)]
+    {
+        let _ = app;
+        log::info!("[dev] Restart `bun run server/standalone.ts` to apply bridge settings");
+    }
+
+    // The port may have changed
+    bridge_client::start(app);
+}
+
 fn stop_bridge(app: &AppHandle) {
     let state = app.state::<AppState>();
     let mut process = state.bridge_process.lock().unwrap();
 
     if let Some(child) = process.take() {
         if let Err(e) = child.kill() {
-            eprintln!("Failed to kill bridge: {}", e);
+            log::error!("Failed to kill bridge: {}", e);
         } else {
-            println!("Bridge stopped");
+            log::info!("Bridge stopped");
         }
     }
 }
 
+/// Whether the bridge reports Live playing or other devices connected. Updates
+/// never prompt, download or restart during a show.
+#[cfg(not(debug_assertions))]
+async fn show_in_progress(app: &AppHandle) -> bool {
+    use bridge::BridgeStatusExt as _;
+
+    let port = ui_port(app);
+    tauri::async_runtime::spawn_blocking(move || bridge::status(port, Duration::from_secs(2)))
+        .await
+        .ok()
+        .and_then(|status| status.ok())
+        .is_some_and(|status| status.show_in_progress())
+}
+
+/// Wait for the update window to be cancelled or closed
+#[cfg(not(debug_assertions))]
+async fn update_cancelled(actions: &mut tokio::sync::mpsc::UnboundedReceiver<update_window::Action>) {
+    while let Some(action) = actions.recv().await {
+        if matches!(action, update_window::Action::Cancel | update_window::Action::Closed) {
+            break;
+        }
+    }
+}
+
+#[cfg(not(debug_assertions))]
+fn show_updates_paused(app: &AppHandle) {
+    app.dialog()
+        .message("Updates are paused while Ableton Live is playing or other devices are connected.\n\nTry again when the show is over.")
+        .title("Updates Paused")
+        .kind(MessageDialogKind::Info)
+        .blocking_show();
+}
+
+/// Check for updates and offer to install. Automatic checks (`manual` false)
+/// stay quiet about skipped versions and while snoozed.
 #[cfg(not(debug_assertions))]
 async fn check_for_updates(app: AppHandle, manual: bool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    // Skip auto-updates for edge builds
-    if env!("CARGO_PKG_VERSION").contains("edge") {
+    if show_in_progress(&app).await {
+        log::info!("Show in progress, not checking for updates");
         if manual {
-            app.dialog()
-                .message("Auto-updates are disabled for edge builds.\n\nDownload the latest edge build from GitHub.")
-                .title("Edge Build")
-                .blocking_show();
+            show_updates_paused(&app);
         }
         return Ok(());
     }
 
-    let updater = app.updater()?;
+    let channel = app.state::<AppState>().settings.lock().unwrap().updates.channel;
+
+    let endpoint = channel.endpoint().parse()?;
+    updates::secure_endpoint(&endpoint)?;
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![endpoint])?
+        .version_comparator(move |current, remote| updates::should_offer(channel, &current, &remote.version))
+        .build()?;
 
-    match updater.check().await {
+    let result = updater.check().await;
+    let available = result.as_ref().ok().and_then(|update| update.as_ref()).map(|update| update.version.clone());
+    *app.state::<AppState>().update_available.lock().unwrap() = available;
+    commands::emit_status_later(&app);
+
+    match result {
         Ok(Some(update)) => {
-            println!("Update available: {}", update.version);
+            log::info!("Update available on {} channel: {}", channel.label(), update.version);
 
-            let should_update = app.dialog()
-                .message(format!(
-                    "Version {} is available (you have {}).\n\nWould you like to install it now?",
-                    update.version,
-                    env!("CARGO_PKG_VERSION")
-                ))
-                .title("Update Available")
-                .buttons(MessageDialogButtons::OkCancelCustom("Install".into(), "Later".into()))
-                .blocking_show();
+            let suppressed = {
+                let settings = app.state::<AppState>().settings.lock().unwrap();
+                updates::is_suppressed(&settings.updates, &update.version, SystemTime::now())
+            };
+            if suppressed && !manual {
+                log::info!("Update {} skipped or snoozed", update.version);
+                return Ok(());
+            }
 
-            if should_update {
-                println!("Downloading update...");
+            // Leaving the edge channel usually means going back to an older release
+            let downgrade = updates::is_downgrade(&update.current_version, &update.version);
+            let (window, mut actions) = update_window::open(&app, &update, channel, downgrade)?;
 
-                // Download and install with progress callbacks
-                update.download_and_install(
-                    |downloaded, total| {
-                        if let Some(total) = total {
-                            let percent = (downloaded as f64 / total as f64 * 100.0) as u32;
-                            println!("Downloading: {}%", percent);
+            match actions.recv().await {
+                Some(update_window::Action::Install) => {}
+                Some(update_window::Action::Skip) => {
+                    log::info!("Skipping update {}", update.version);
+                    let version = update.version.clone();
+                    update_settings(&app, |settings| {
+                        if !settings.updates.skipped_versions.contains(&version) {
+                            settings.updates.skipped_versions.push(version);
                         }
-                    },
-                    || {
-                        println!("Download complete, installing...");
-                    }
-                ).await?;
+                    });
+                    window.close();
+                    return Ok(());
+                }
+                Some(update_window::Action::Snooze) => {
+                    log::info!("Snoozing update prompts");
+                    let until = updates::unix_secs(SystemTime::now() + updates::SNOOZE_DURATION);
+                    update_settings(&app, |settings| settings.updates.snoozed_until = Some(until));
+                    window.close();
+                    return Ok(());
+                }
+                _ => {
+                    log::info!("Update postponed");
+                    window.close();
+                    return Ok(());
+                }
+            }
+
+            // The show may have started while the prompt was open
+            if show_in_progress(&app).await {
+                window.close();
+                show_updates_paused(&app);
+                return Ok(());
+            }
+
+            log::info!("Downloading update...");
 
-                println!("Update installed, restarting...");
-                app.restart();
+            let mut downloaded: u64 = 0;
+            let download = update.download(
+                |chunk, total| {
+                    downloaded += chunk as u64;
+                    window.set_progress(downloaded, total);
+                },
+                || {
+                    log::info!("Download complete, installing...");
+                },
+            );
+
+            // Closing the window counts as cancelling; dropping the future stops the download
+            let bytes = tokio::select! {
+                result = download => result?,
+                _ = update_cancelled(&mut actions) => {
+                    log::info!("Update download cancelled");
+                    window.close();
+                    return Ok(());
+                }
+            };
+
+            // Installing restarts the app (and the bridge), so hold off until the show ends
+            if show_in_progress(&app).await {
+                log::info!("Show in progress, waiting to install update");
+                window.set_waiting();
+                let show_over = async {
+                    while show_in_progress(&app).await {
+                        tokio::time::sleep(updates::BUSY_POLL_INTERVAL).await;
+                    }
+                };
+                tokio::select! {
+                    _ = show_over => {}
+                    _ = update_cancelled(&mut actions) => {
+                        log::info!("Update install cancelled");
+                        window.close();
+                        return Ok(());
+                    }
+                }
             }
+
+            window.set_installing();
+            update.install(bytes)?;
+
+            log::info!("Update installed, restarting...");
+            app.restart();
         }
         Ok(None) => {
-            println!("No updates available");
+            log::info!("No updates available");
             if manual {
                 app.dialog()
                     .message("You're running the latest version.")
@@ -489,7 +978,7 @@ async fn check_for_updates(app: AppHandle, manual: bool) -> Result<(), Box<dyn s
             }
         }
         Err(e) => {
-            eprintln!("Update check error: {}", e);
+            log::error!("Update check error: {}", e);
             if manual {
                 app.dialog()
                     .message(format!("Failed to check for updates:\n\n{}", e))
@@ -504,67 +993,287 @@ async fn check_for_updates(app: AppHandle, manual: bool) -> Result<(), Box<dyn s
     Ok(())
 }
 
-fn get_remote_scripts_path() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+/// Install a signed update bundle picked from disk, for machines without internet access
+#[cfg(not(debug_assertions))]
+async fn install_update_from_file(app: &AppHandle) -> Result<(), String> {
+    let Some(bundle) = offline_update::pick(app)? else {
+        return Ok(());
+    };
+    updates::verify_bundle(&bundle.bytes, &bundle.signature, &offline_update::pubkey(app)?)?;
+    log::info!("Verified update bundle {}", bundle.file_name());
+
+    if show_in_progress(app).await {
+        show_updates_paused(app);
+        return Ok(());
+    }
 
-    #[cfg(target_os = "macos")]
-    let path = home.join("Music/Ableton/User Library/Remote Scripts");
+    let version = bundle.version.as_ref().map_or("this update".to_string(), |v| format!("version {}", v));
+    let confirmed = app.dialog()
+        .message(format!(
+            "{} is signed by Mission Control.\n\nInstall {}? Mission Control will restart.",
+            bundle.file_name(), version
+        ))
+        .title("Install Update")
+        .kind(MessageDialogKind::Info)
+        .buttons(MessageDialogButtons::OkCancelCustom("Install".to_string(), "Cancel".to_string()))
+        .blocking_show();
+    if !confirmed {
+        return Ok(());
+    }
 
-    #[cfg(target_os = "windows")]
-    let path = home.join("Documents/Ableton/User Library/Remote Scripts");
+    offline_update::install(app, bundle).await?;
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    return Err("Unsupported operating system".to_string());
+    log::info!("Update installed, restarting...");
+    app.restart();
+}
 
-    Ok(path)
+fn settings_path(app: &AppHandle) -> Result<PathBuf, String> {
+    Settings::path(&app.config().identifier)
 }
 
-fn install_remote_script(app: &AppHandle) -> Result<String, String> {
-    // Get source path (bundled AbletonOSC)
-    let resource_path = app
-        .path()
-        .resource_dir()
-        .map_err(|e| format!("Could not get resource dir: {}", e))?
-        .join("AbletonOSC");
+/// Change the settings and save them
+fn update_settings(app: &AppHandle, change: impl FnOnce(&mut Settings)) {
+    let state = app.state::<AppState>();
+    let mut settings = state.settings.lock().unwrap();
+    change(&mut settings);
+    if let Err(e) = settings_path(app).and_then(|path| settings.save(&path)) {
+        log::error!("Failed to save settings: {}", e);
+    }
+}
 
-    // Get destination path
-    let dest_dir = get_remote_scripts_path()?;
-    let dest_path = dest_dir.join("AbletonOSC");
+/// Persist the update channel and check it right away
+#[cfg(not(debug_assertions))]
+fn set_update_channel(app: &AppHandle, channel: UpdateChannel) {
+    update_settings(app, |settings| settings.updates.channel = channel);
+    check_channel_item(app, channel);
 
-    // Create Remote Scripts directory if it doesn't exist
-    fs::create_dir_all(&dest_dir)
-        .map_err(|e| format!("Could not create Remote Scripts directory: {}", e))?;
+    log::info!("Update channel set to {}", channel.label());
 
-    // Remove existing installation if present
-    if dest_path.exists() {
-        fs::remove_dir_all(&dest_path)
-            .map_err(|e| format!("Could not remove existing installation: {}", e))?;
-    }
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = check_for_updates(handle, true).await {
+            log::error!("Update check failed: {}", e);
+        }
+    });
+}
 
-    // Copy AbletonOSC to Remote Scripts
-    copy_dir_recursive(&resource_path, &dest_path)?;
+/// Tick the tray item for `channel`
+#[cfg(not(debug_assertions))]
+fn check_channel_item(app: &AppHandle, channel: UpdateChannel) {
+    // Clicking a check item toggles it, so set both explicitly
+    let tray = app.state::<TrayMenu>();
+    let _ = tray.channel_stable.set_checked(channel == UpdateChannel::Stable);
+    let _ = tray.channel_edge.set_checked(channel == UpdateChannel::Edge);
+}
+
+/// Open the Settings window and apply changes saved from it
+fn open_settings(app: &AppHandle) {
+    let settings = app.state::<AppState>().settings.lock().unwrap().clone();
+    let default_path = remote_script::default_remote_scripts_path().ok().map(|path| path.to_string_lossy().to_string());
 
-    println!("Remote script installed to: {:?}", dest_path);
+    let (window, mut actions) = match settings_window::open(app, &settings, default_path) {
+        Ok(Some(opened)) => opened,
+        Ok(None) => return,
+        Err(e) => {
+            log::error!("{}", e);
+            return;
+        }
+    };
 
-    Ok(dest_path.to_string_lossy().to_string())
+    let handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(action) = actions.recv().await {
+            match action {
+                settings_window::Action::Save(Ok(edited)) => match apply_settings(&handle, edited) {
+                    Ok(message) => window.saved(&message),
+                    Err(errors) => window.show_errors(&errors),
+                },
+                settings_window::Action::Save(Err(e)) => window.show_error(&e),
+                settings_window::Action::Browse => {
+                    let folder = handle.dialog()
+                        .file()
+                        .set_title("Choose Remote Scripts Folder")
+                        .blocking_pick_folder()
+                        .and_then(|folder| folder.into_path().ok());
+                    if let Some(folder) = folder {
+                        window.set_remote_scripts_path(&folder.to_string_lossy());
+                    }
+                }
+                settings_window::Action::Learn(field) => {
+                    // Waits a while, so don't hold up other actions
+                    let (handle, window) = (handle.clone(), window.clone());
+                    tauri::async_runtime::spawn(async move {
+                        window.set_learned(&field, midi::learn(&handle).await);
+                    });
+                }
+                settings_window::Action::Close => window.close(),
+                settings_window::Action::Closed => break,
+            }
+        }
+    });
 }
 
-fn copy_dir_recursive(src: &PathBuf, dst: &PathBuf) -> Result<(), String> {
-    fs::create_dir_all(dst)
-        .map_err(|e| format!("Could not create directory {:?}: {}", dst, e))?;
+/// Validate, save and apply settings from the Settings window, restarting the
+/// bridge if needed. Returns a status message for the window.
+fn apply_settings(app: &AppHandle, mut edited: Settings) -> Result<String, Vec<FieldError>> {
+    let current = app.state::<AppState>().settings.lock().unwrap().clone();
+    let running = effective_settings(app);
+
+    // Not edited in the window, and an update prompt may have changed them since it opened
+    edited.updates.skipped_versions = current.updates.skipped_versions.clone();
+    edited.updates.snoozed_until = current.updates.snoozed_until;
 
-    for entry in fs::read_dir(src).map_err(|e| format!("Could not read dir: {}", e))? {
-        let entry = entry.map_err(|e| format!("Could not read entry: {}", e))?;
-        let path = entry.path();
-        let dest_path = dst.join(entry.file_name());
+    let mut applied = edited.clone();
+    app.state::<AppState>().cli.apply(&mut applied);
 
-        if path.is_dir() {
-            copy_dir_recursive(&path, &dest_path)?;
+    let mut errors = edited.validate();
+    errors.extend(hotkeys::validate(&edited.shortcuts));
+    if errors.is_empty() && applied.bridge.ui_port != running.bridge.ui_port {
+        let report = diagnostics::check_port(diagnostics::Protocol::Tcp, edited.bridge.ui_port, diagnostics::PortRole::BridgeUi);
+        if let Some(problem) = report.problem() {
+            errors.push(FieldError { field: "bridge.uiPort".into(), message: problem });
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    if edited == current {
+        return Ok("No changes".to_string());
+    }
+
+    settings_path(app)
+        .and_then(|path| edited.save(&path))
+        .map_err(|message| vec![FieldError { field: "".into(), message }])?;
+    *app.state::<AppState>().settings.lock().unwrap() = edited.clone();
+    log::info!("[settings] Saved");
+
+    #[cfg(not(debug_assertions))]
+    if edited.updates.channel != current.updates.channel {
+        check_channel_item(app, edited.updates.channel);
+    }
+
+    if edited.ui.kiosk != current.ui.kiosk || (edited.ui.kiosk && edited.ui.kiosk_monitor != current.ui.kiosk_monitor) {
+        if edited.ui.kiosk {
+            start_kiosk(app);
         } else {
-            fs::copy(&path, &dest_path)
-                .map_err(|e| format!("Could not copy {:?}: {}", path, e))?;
+            kiosk::stop(app);
+            let _ = app.state::<TrayMenu>().kiosk.set_checked(false);
         }
     }
 
-    Ok(())
+    if edited.ui.always_on_top != current.ui.always_on_top {
+        let _ = app.state::<TrayMenu>().always_on_top.set_checked(edited.ui.always_on_top);
+        if let Some(window) = app.get_webview_window(ui_window::LABEL) {
+            let _ = window.set_always_on_top(edited.ui.always_on_top);
+        }
+    }
+
+    if edited.ableton.link != current.ableton.link {
+        link::set_enabled(app, edited.ableton.link);
+    }
+
+    if edited.midi != current.midi {
+        midi::start(app, &edited.midi);
+    }
+
+    if edited.relay != current.relay {
+        osc_relay::start(app, &edited.relay);
+    }
+    // Before restarting the bridge, which may need the port the proxy had
+    if applied.relay != running.relay || applied.bridge != running.bridge {
+        osc_proxy::start(app, &applied);
+    }
+
+    if edited.osc_control != current.osc_control {
+        osc_control::start(app, &edited.osc_control);
+    }
+
+    let unregistered = if edited.shortcuts != current.shortcuts {
+        hotkeys::register(app, &edited.shortcuts)
+    } else {
+        Vec::new()
+    };
+    if !unregistered.is_empty() {
+        return Ok(format!("Saved, but some shortcuts don't work: {}.", unregistered.join("; ")));
+    }
+
+    let overridden = if applied.bridge != edited.bridge {
+        " Command-line flags still override some bridge settings until the app restarts."
+    } else {
+        ""
+    };
+
+    if running.needs_bridge_restart(&applied) {
+        restart_bridge(app);
+        if cfg!(debug_assertions) {
+            return Ok("Saved. Restart the dev bridge to apply bridge settings.".to_string());
+        }
+        return Ok(format!("Saved. The bridge restarted on port {}.{}", applied.bridge.ui_port, overridden));
+    }
+
+    if overridden.is_empty() {
+        Ok("Saved".to_string())
+    } else {
+        Ok(format!("Saved.{}", overridden))
+    }
+}
+
+/// Ask where to save a diagnostics bundle, then write it there
+fn create_diagnostics_bundle(app: &AppHandle) {
+    let mut picker = app
+        .dialog()
+        .file()
+        .set_title("Save Diagnostics Bundle")
+        .set_file_name("Mission Control Diagnostics.zip")
+        .add_filter("Zip Archive", &["zip"]);
+    if let Some(desktop) = dirs::desktop_dir() {
+        picker = picker.set_directory(desktop);
+    }
+    let Some(file) = picker.blocking_save_file() else {
+        return;
+    };
+
+    let inputs = diagnostics_bundle::Inputs {
+        app_version: app.package_info().version.to_string(),
+        settings: effective_settings(app),
+        ui_port: ui_port(app),
+        bridge_started: app.state::<AppState>().bridge_process.lock().unwrap().is_some(),
+        resource_dir: app.path().resource_dir().ok(),
+    };
+    let result = file
+        .into_path()
+        .map_err(|e| format!("Invalid location: {}", e))
+        .and_then(|path| diagnostics_bundle::write(&path, &inputs).map(|_| path));
+
+    match result {
+        Ok(path) => {
+            log::info!("[diagnostics] Wrote bundle to {}", path.display());
+            app.dialog()
+                .message(format!(
+                    "Saved to {}\n\nAttach it to your bug report. Passwords and tokens have been removed, but it includes your computer's network addresses and folder names.",
+                    path.display()
+                ))
+                .title("Diagnostics Bundle Created")
+                .kind(MessageDialogKind::Info)
+                .blocking_show();
+        }
+        Err(e) => {
+            log::error!("[diagnostics] {}", e);
+            app.dialog()
+                .message(format!("Could not create the diagnostics bundle:\n\n{}", e))
+                .title("Error")
+                .kind(MessageDialogKind::Error)
+                .blocking_show();
+        }
+    }
+}
+
+fn install_remote_script(app: &AppHandle) -> Result<String, String> {
+    let resource_dir = app
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("Could not get resource dir: {}", e))?;
+    let remote_scripts = remote_script::remote_scripts_path(&effective_settings(app))?;
+
+    remote_script::install(&resource_dir, &remote_scripts).map(|path| path.to_string_lossy().to_string())
 }
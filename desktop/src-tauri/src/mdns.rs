@@ -0,0 +1,50 @@
+//! Advertising the web UI on the local network over mDNS/DNS-SD
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+/// Browsers and "open on LAN" apps look for plain HTTP services
+const SERVICE_TYPE: &str = "_http._tcp.local.";
+
+/// Advertises the UI until dropped
+pub struct Advertiser {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Advertiser {
+    /// Advertise `http://<this host>:<port>/` as "Mission Control (<host name>)"
+    pub fn start(port: u16) -> Result<Advertiser, String> {
+        let host_name = sysinfo::System::host_name().unwrap_or_else(|| "mission-control".to_string());
+        let instance = format!("Mission Control ({})", host_name);
+
+        let daemon = ServiceDaemon::new().map_err(|e| format!("Could not start mDNS: {}", e))?;
+        let service = ServiceInfo::new(
+            SERVICE_TYPE,
+            &instance,
+            &format!("{}.local.", host_name),
+            "",
+            port,
+            &[("path", "/")][..],
+        )
+        .map_err(|e| format!("Could not create mDNS service: {}", e))?
+        .enable_addr_auto();
+
+        let fullname = service.get_fullname().to_string();
+        daemon
+            .register(service)
+            .map_err(|e| format!("Could not advertise on the network: {}", e))?;
+        log::info!("[mdns] Advertising \"{}\" on port {}", instance, port);
+
+        Ok(Advertiser { daemon, fullname })
+    }
+}
+
+impl Drop for Advertiser {
+    fn drop(&mut self) {
+        // Say goodbye so browsers drop the entry now instead of when it expires
+        if let Ok(status) = self.daemon.unregister(&self.fullname) {
+            let _ = status.recv_timeout(std::time::Duration::from_secs(1));
+        }
+        let _ = self.daemon.shutdown();
+    }
+}
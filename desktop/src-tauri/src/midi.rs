@@ -0,0 +1,462 @@
+//! MIDI: listens on the chosen inputs and sends what the active profile maps
+//! each note or CC to (see `midi_mapping`), and lights the controller's pads
+//! through the chosen output (see `midi_feedback`)
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use midir::{MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use mission_control_protocol::{ClientMessage, SessionState, Snapshot};
+use tauri::{AppHandle, Manager};
+use tokio::sync::oneshot;
+
+use crate::bridge_client;
+use crate::midi_feedback;
+use crate::midi_mapping::{self, FeedbackMode, MidiMapping, MidiSettings, SessionBox, Trigger};
+
+/// Client name other MIDI apps see, and the virtual input's name
+pub const CLIENT_NAME: &str = "Mission Control";
+
+/// How long MIDI learn waits for a note or CC
+pub const LEARN_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Default)]
+pub struct Midi {
+    /// Port names and connections, open while they're in use. Dropping one
+    /// closes it.
+    connections: Mutex<Vec<(String, MidiInputConnection<()>)>>,
+    /// The active profile's mappings
+    mappings: Mutex<HashMap<Trigger, MidiMapping>>,
+    /// Waiting for MIDI learn, which takes the next note or CC instead of it
+    /// being mapped
+    learner: Mutex<Option<oneshot::Sender<Trigger>>>,
+    session_box: Mutex<SessionBox>,
+    feedback: Mutex<Feedback>,
+}
+
+/// Where LED messages go, and what was last sent for each pad so only changes
+/// are sent
+#[derive(Default)]
+struct Feedback {
+    outputs: Vec<MidiOutputConnection>,
+    mode: FeedbackMode,
+    sent: HashMap<Trigger, Vec<[u8; 3]>>,
+}
+
+/// Names of the input ports, for choosing in Settings
+pub fn input_ports() -> Vec<String> {
+    let Ok(input) = MidiInput::new(CLIENT_NAME) else {
+        return Vec::new();
+    };
+    input
+        .ports()
+        .iter()
+        .filter_map(|port| input.port_name(port).ok())
+        // Our own virtual input, when it's running
+        .filter(|name| !name.contains(CLIENT_NAME))
+        .collect()
+}
+
+/// Names of the output ports, for choosing in Settings
+pub fn output_ports() -> Vec<String> {
+    let Ok(output) = MidiOutput::new(CLIENT_NAME) else {
+        return Vec::new();
+    };
+    output
+        .ports()
+        .iter()
+        .filter_map(|port| output.port_name(port).ok())
+        .filter(|name| !name.contains(CLIENT_NAME))
+        .collect()
+}
+
+/// Listen on the inputs in `settings` with its active profile, and light pads
+/// through its output, closing any ports opened before
+pub fn start(app: &AppHandle, settings: &MidiSettings) {
+    let midi = app.state::<Midi>();
+    midi.connections.lock().unwrap().clear();
+    // Ports may not open twice (on Windows), so close the output before it reopens
+    drop(std::mem::take(&mut midi.feedback.lock().unwrap().outputs));
+
+    let mappings = settings
+        .active_profile()
+        .map(|profile| profile.mappings.iter().map(|mapping| (mapping.trigger, mapping.clone())).collect())
+        .unwrap_or_default();
+    *midi.mappings.lock().unwrap() = mappings;
+
+    let mut connections = Vec::new();
+    for name in &settings.inputs {
+        match connect(app, name) {
+            Ok(connection) => connections.push((name.clone(), connection)),
+            Err(e) => log::warn!("[midi] {}", e),
+        }
+    }
+    if settings.virtual_input {
+        match connect_virtual(app) {
+            Ok(connection) => connections.push((CLIENT_NAME.to_string(), connection)),
+            Err(e) => log::warn!("[midi] {}", e),
+        }
+    }
+    if !connections.is_empty() {
+        log::info!("[midi] Listening on {} input(s) with profile {:?}", connections.len(), settings.profile);
+    }
+    *midi.connections.lock().unwrap() = connections;
+
+    let mut outputs = Vec::new();
+    if let Some(name) = &settings.output {
+        match connect_output(name) {
+            Ok(output) => outputs.push(output),
+            Err(e) => log::warn!("[midi] {}", e),
+        }
+    }
+    if settings.virtual_output {
+        match connect_virtual_output() {
+            Ok(output) => outputs.push(output),
+            Err(e) => log::warn!("[midi] {}", e),
+        }
+    }
+    if settings.feedback == FeedbackMode::Launchpad {
+        for output in &mut outputs {
+            for message in midi_feedback::LAUNCHPAD_PROGRAMMER_MODE {
+                let _ = output.send(message);
+            }
+        }
+    }
+    *midi.feedback.lock().unwrap() = Feedback {
+        outputs,
+        mode: settings.feedback,
+        sent: HashMap::new(),
+    };
+    refresh(app);
+}
+
+fn connect(app: &AppHandle, name: &str) -> Result<MidiInputConnection<()>, String> {
+    let input = MidiInput::new(CLIENT_NAME).map_err(|e| format!("Could not open MIDI: {}", e))?;
+    let port = input
+        .ports()
+        .into_iter()
+        .find(|port| input.port_name(port).is_ok_and(|port_name| port_name == name))
+        .ok_or_else(|| format!("MIDI input {:?} not found", name))?;
+
+    let app = app.clone();
+    input
+        .connect(&port, CLIENT_NAME, move |_, bytes, _| receive(&app, bytes), ())
+        .map_err(|e| format!("Could not open MIDI input {:?}: {}", name, e))
+}
+
+#[cfg(unix)]
+fn connect_virtual(app: &AppHandle) -> Result<MidiInputConnection<()>, String> {
+    use midir::os::unix::VirtualInput;
+
+    let input = MidiInput::new(CLIENT_NAME).map_err(|e| format!("Could not open MIDI: {}", e))?;
+    let app = app.clone();
+    input
+        .create_virtual(CLIENT_NAME, move |_, bytes, _| receive(&app, bytes), ())
+        .map_err(|e| format!("Could not create virtual MIDI input: {}", e))
+}
+
+#[cfg(not(unix))]
+fn connect_virtual(_app: &AppHandle) -> Result<MidiInputConnection<()>, String> {
+    Err("Virtual MIDI ports aren't supported on this platform".to_string())
+}
+
+fn connect_output(name: &str) -> Result<MidiOutputConnection, String> {
+    let output = MidiOutput::new(CLIENT_NAME).map_err(|e| format!("Could not open MIDI: {}", e))?;
+    let port = output
+        .ports()
+        .into_iter()
+        .find(|port| output.port_name(port).is_ok_and(|port_name| port_name == name))
+        .ok_or_else(|| format!("MIDI output {:?} not found", name))?;
+    output
+        .connect(&port, CLIENT_NAME)
+        .map_err(|e| format!("Could not open MIDI output {:?}: {}", name, e))
+}
+
+#[cfg(unix)]
+fn connect_virtual_output() -> Result<MidiOutputConnection, String> {
+    use midir::os::unix::VirtualOutput;
+
+    let output = MidiOutput::new(CLIENT_NAME).map_err(|e| format!("Could not open MIDI: {}", e))?;
+    output
+        .create_virtual(CLIENT_NAME)
+        .map_err(|e| format!("Could not create virtual MIDI output: {}", e))
+}
+
+#[cfg(not(unix))]
+fn connect_virtual_output() -> Result<MidiOutputConnection, String> {
+    Err("Virtual MIDI ports aren't supported on this platform".to_string())
+}
+
+impl Midi {
+    /// The mapping for a message from an input, and its value. `None` if it
+    /// isn't a note or CC, isn't mapped, or MIDI learn takes it.
+    fn mapping(&self, bytes: &[u8]) -> Option<(MidiMapping, u8)> {
+        let (trigger, value) = midi_mapping::parse(bytes)?;
+        if let Some(learner) = self.learner.lock().unwrap().take() {
+            let _ = learner.send(trigger);
+            return None;
+        }
+        let mapping = self.mappings.lock().unwrap().get(&trigger).cloned()?;
+        Some((mapping, value))
+    }
+
+    /// What `mapping` sends for `value`, moving the session box if it does.
+    /// Also returns whether the box moved, and so the pads need lighting again.
+    fn act(&self, mapping: &MidiMapping, value: u8, session: &SessionState) -> (Vec<ClientMessage>, bool) {
+        let mut session_box = self.session_box.lock().unwrap();
+        let messages = midi_mapping::messages(&mapping.action, value, session, *session_box);
+        let moved = if value > 0 { session_box.moved(&mapping.action, session) } else { None };
+        match moved.filter(|moved| moved != &*session_box) {
+            Some(moved) => {
+                log::info!("[midi] Session box at track {}, scene {}", moved.track + 1, moved.scene + 1);
+                *session_box = moved;
+                (messages, true)
+            }
+            None => (messages, false),
+        }
+    }
+}
+
+/// Handle a message from any input
+fn receive(app: &AppHandle, bytes: &[u8]) {
+    let midi = app.state::<Midi>();
+    let Some((mapping, value)) = midi.mapping(bytes) else {
+        return;
+    };
+    let (messages, moved) = bridge_client::with_snapshot(app, |snapshot| {
+        snapshot.live_session().map(|session| midi.act(&mapping, value, session))
+    })
+    .flatten()
+    .unwrap_or_default();
+
+    if moved {
+        refresh(app);
+    }
+    for message in messages {
+        if let Err(e) = bridge_client::send(app, message) {
+            log::warn!("[midi] {}", e);
+            return;
+        }
+    }
+}
+
+/// Light the pads for the latest state
+fn refresh(app: &AppHandle) {
+    if let Some(leds) = bridge_client::with_snapshot(app, |snapshot| leds(app, snapshot)).flatten() {
+        show(app, leds);
+    }
+}
+
+/// The LED messages for every mapped pad, to pass to [`show`] once done reading
+/// `snapshot`. `None` with no output to send them to.
+pub fn leds(app: &AppHandle, snapshot: &Snapshot) -> Option<HashMap<Trigger, Vec<[u8; 3]>>> {
+    app.state::<Midi>().leds(snapshot.live_session())
+}
+
+/// Send the LED messages for pads that changed since last time
+pub fn show(app: &AppHandle, leds: HashMap<Trigger, Vec<[u8; 3]>>) {
+    app.state::<Midi>().show(leds);
+}
+
+impl Midi {
+    fn leds(&self, session: Option<&SessionState>) -> Option<HashMap<Trigger, Vec<[u8; 3]>>> {
+        let mode = {
+            let feedback = self.feedback.lock().unwrap();
+            if feedback.outputs.is_empty() {
+                return None;
+            }
+            feedback.mode
+        };
+        let session_box = *self.session_box.lock().unwrap();
+        let mappings = self.mappings.lock().unwrap();
+        Some(midi_feedback::leds(mappings.values(), mode, session_box, session))
+    }
+
+    fn show(&self, leds: HashMap<Trigger, Vec<[u8; 3]>>) {
+        let mut feedback = self.feedback.lock().unwrap();
+        let Feedback { outputs, sent, .. } = &mut *feedback;
+        for (trigger, messages) in leds {
+            if sent.get(&trigger) == Some(&messages) {
+                continue;
+            }
+            for output in outputs.iter_mut() {
+                for message in &messages {
+                    if let Err(e) = output.send(message) {
+                        log::warn!("[midi] Could not send LED message: {}", e);
+                    }
+                }
+            }
+            sent.insert(trigger, messages);
+        }
+    }
+}
+
+/// Wait for the next note or CC from any input, for up to [`LEARN_TIMEOUT`].
+/// Listens on every input port while waiting, not only the chosen ones.
+pub async fn learn(app: &AppHandle) -> Option<Trigger> {
+    let (sender, receiver) = oneshot::channel();
+    let midi = app.state::<Midi>();
+    // Replaces (and so cancels) any learn already waiting
+    *midi.learner.lock().unwrap() = Some(sender);
+
+    // Ports already open may not open twice (on Windows), but they already
+    // feed the learner
+    let listening: Vec<_> = midi.connections.lock().unwrap().iter().map(|(name, _)| name.clone()).collect();
+    let extra: Vec<_> = input_ports()
+        .into_iter()
+        .filter(|name| !listening.contains(name))
+        .filter_map(|name| connect(app, &name).ok())
+        .collect();
+
+    let learned = tokio::time::timeout(LEARN_TIMEOUT, receiver).await.ok().and_then(Result::ok);
+    midi.learner.lock().unwrap().take();
+    drop(extra);
+    learned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mission_control_protocol::{Scene, Track};
+
+    /// Listening with the Launchpad profile
+    fn launchpad() -> Midi {
+        let midi = Midi::default();
+        *midi.mappings.lock().unwrap() =
+            midi_mapping::launchpad_profile().mappings.into_iter().map(|mapping| (mapping.trigger, mapping)).collect();
+        midi
+    }
+
+    /// Tracks from id 100 and scenes from id 200
+    fn session(tracks: i32, scenes: i32) -> SessionState {
+        SessionState {
+            tracks: (0..tracks).map(|index| Track { id: 100 + index, ..Track::default() }).collect(),
+            scenes: (0..scenes).map(|index| Scene { id: 200 + index, ..Scene::default() }).collect(),
+            ..SessionState::default()
+        }
+    }
+
+    /// What the bridge is sent for `bytes`, and whether the session box moved
+    fn received(midi: &Midi, bytes: &[u8], session: &SessionState) -> (Vec<ClientMessage>, bool) {
+        midi.mapping(bytes).map(|(mapping, value)| midi.act(&mapping, value, session)).unwrap_or_default()
+    }
+
+    #[test]
+    fn sends_what_the_profile_maps_to() {
+        let midi = launchpad();
+        let session = session(10, 10);
+        // The top left pad, then the top scene button
+        assert_eq!(received(&midi, &[0x90, 81, 127], &session), (vec![ClientMessage::ClipFire { track_id: 100, scene_id: 200 }], false));
+        assert_eq!(received(&midi, &[0xB0, 89, 127], &session), (vec![ClientMessage::SceneFire { scene_id: 200 }], false));
+        // Released, unmapped, and not a note or CC
+        assert_eq!(received(&midi, &[0xB0, 89, 0], &session), (vec![], false));
+        assert_eq!(received(&midi, &[0x91, 81, 127], &session), (vec![], false));
+        assert_eq!(received(&midi, &[0xE0, 0, 64], &session), (vec![], false));
+    }
+
+    #[test]
+    fn moves_the_session_box_with_the_arrows() {
+        let midi = launchpad();
+        let session = session(10, 10);
+        // Down, then right, then the top left pad
+        assert_eq!(received(&midi, &[0xB0, 92, 127], &session), (vec![], true));
+        assert_eq!(received(&midi, &[0xB0, 92, 0], &session), (vec![], false));
+        assert_eq!(received(&midi, &[0xB0, 94, 127], &session), (vec![], true));
+        assert_eq!(received(&midi, &[0x90, 81, 127], &session), (vec![ClientMessage::ClipFire { track_id: 101, scene_id: 201 }], false));
+        // Back up, then already at the top
+        assert_eq!(received(&midi, &[0xB0, 91, 127], &session), (vec![], true));
+        assert_eq!(received(&midi, &[0xB0, 91, 127], &session), (vec![], false));
+    }
+
+    #[test]
+    fn learn_takes_the_next_trigger() {
+        let midi = launchpad();
+        let (sender, mut receiver) = oneshot::channel();
+        *midi.learner.lock().unwrap() = Some(sender);
+
+        assert_eq!(received(&midi, &[0x90, 81, 127], &session(1, 1)), (vec![], false));
+        let trigger = Trigger { kind: midi_mapping::TriggerKind::Note, channel: 1, number: 81 };
+        assert_eq!(receiver.try_recv(), Ok(trigger));
+        // Then mapping resumes
+        assert_eq!(received(&midi, &[0x90, 81, 127], &session(1, 1)).0.len(), 1);
+    }
+
+    /// A note from a virtual output reaches an input as a trigger
+    #[cfg(unix)]
+    #[test]
+    #[ignore = "needs a MIDI system that can create virtual ports (ALSA or CoreMIDI)"]
+    fn receives_from_a_virtual_port() {
+        use midir::os::unix::VirtualOutput;
+        use midi_mapping::TriggerKind;
+
+        const PORT: &str = "Mission Control Test Output";
+        let mut output = MidiOutput::new(CLIENT_NAME).unwrap().create_virtual(PORT).unwrap();
+        let input = MidiInput::new("Mission Control Test").unwrap();
+        let port = input
+            .ports()
+            .into_iter()
+            .find(|port| input.port_name(port).is_ok_and(|name| name.contains(PORT)))
+            .expect("the virtual output should be listed");
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _connection = input
+            .connect(&port, "test", move |_, bytes, _| {
+                if let Some(parsed) = midi_mapping::parse(bytes) {
+                    let _ = sender.send(parsed);
+                }
+            }, ())
+            .unwrap();
+
+        output.send(&[0x80, 36, 0]).unwrap();
+        output.send(&[0x92, 36, 100]).unwrap();
+        let trigger = Trigger { kind: TriggerKind::Note, channel: 3, number: 36 };
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(2)), Ok((trigger, 100)));
+    }
+
+    /// Pads light through an output connected to a virtual input that records
+    /// what it's sent, and only changes are sent again
+    #[cfg(unix)]
+    #[test]
+    #[ignore = "needs a MIDI system that can create virtual ports (ALSA or CoreMIDI)"]
+    fn lights_pads_through_an_output() {
+        use midir::os::unix::VirtualInput;
+        use midi_mapping::TriggerKind;
+
+        const PORT: &str = "Mission Control Test Input";
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let _recorder = MidiInput::new("Mission Control Test")
+            .unwrap()
+            .create_virtual(PORT, move |_, bytes, _| {
+                let _ = sender.send(bytes.to_vec());
+            }, ())
+            .unwrap();
+        let name = output_ports().into_iter().find(|name| name.contains(PORT)).expect("the virtual input should be listed");
+
+        // Scene 1's button and the pad below it on a generic controller
+        let scene = Trigger { kind: TriggerKind::Cc, channel: 1, number: 89 };
+        let pad = Trigger { kind: TriggerKind::Note, channel: 1, number: 71 };
+        let midi = launchpad();
+        midi.mappings.lock().unwrap().retain(|trigger, _| [scene, pad].contains(trigger));
+        *midi.feedback.lock().unwrap() = Feedback {
+            outputs: vec![connect_output(&name).unwrap()],
+            mode: FeedbackMode::Generic,
+            sent: HashMap::new(),
+        };
+        let recorded = || {
+            let mut messages: Vec<Vec<u8>> = receiver.recv_timeout(Duration::from_secs(2)).into_iter().collect();
+            messages.extend(receiver.recv_timeout(Duration::from_millis(200)));
+            messages.sort();
+            messages
+        };
+
+        let session = session(1, 2);
+        midi.show(midi.leds(Some(&session)).unwrap());
+        // A stopped scene, and no clip on track 1 in scene 2
+        assert_eq!(recorded(), [vec![0x90, 71, 0], vec![0xB0, 89, midi_feedback::GENERIC_STOPPED]]);
+
+        midi.show(midi.leds(Some(&session)).unwrap());
+        midi.show(midi.leds(None).unwrap());
+        assert_eq!(recorded(), [vec![0xB0, 89, 0]]);
+    }
+}
@@ -0,0 +1,287 @@
+//! LED feedback: lights the pads and scene buttons mapped in the active profile
+//! to show the clips in the session box
+//!
+//! Feedback goes to the control that fires the clip, so it follows whatever
+//! layout the profile has.
+
+use std::collections::HashMap;
+
+use mission_control_protocol::SessionState;
+
+use crate::midi_mapping::{FeedbackMode, MidiAction, MidiMapping, SessionBox, Trigger, TriggerKind};
+use crate::scene_menu::{self, SceneState};
+
+/// Puts a Launchpad X and Launchpad Mini MK3 in Programmer mode, which the
+/// Launchpad profile expects. Other devices ignore them.
+pub const LAUNCHPAD_PROGRAMMER_MODE: &[&[u8]] = &[
+    &[0xF0, 0x00, 0x20, 0x29, 0x02, 0x0C, 0x0E, 0x01, 0xF7],
+    &[0xF0, 0x00, 0x20, 0x29, 0x02, 0x0D, 0x0E, 0x01, 0xF7],
+];
+
+/// Launchpad Programmer mode channels (from 0): solid, flashing between the
+/// solid color and this one, and pulsing
+const SOLID: u8 = 0;
+const FLASHING: u8 = 1;
+const PULSING: u8 = 2;
+
+/// Launchpad palette indexes
+const GREEN: u8 = 21;
+const RED: u8 = 5;
+
+/// Generic mode velocities, on the mapping's own channel
+pub const GENERIC_STOPPED: u8 = 32;
+pub const GENERIC_TRIGGERED: u8 = 64;
+pub const GENERIC_RECORDING: u8 = 96;
+pub const GENERIC_PLAYING: u8 = 127;
+
+/// The first 64 entries of the Launchpad palette, which cover every hue at four
+/// brightnesses. Later entries repeat these hues, so nearest-color matching
+/// doesn't need them.
+const LAUNCHPAD_PALETTE: [u32; 64] = [
+    0x000000, 0x1C1C1C, 0x7C7C7C, 0xFCFCFC, 0xFF4C4C, 0xFF0000, 0x590000, 0x190000,
+    0xFFBD6C, 0xFF5400, 0x591D00, 0x271B00, 0xFFFF4C, 0xFFFF00, 0x595900, 0x191900,
+    0x88FF4C, 0x54FF00, 0x1D5900, 0x142B00, 0x4CFF4C, 0x00FF00, 0x005900, 0x001900,
+    0x4CFF5E, 0x00FF19, 0x00590D, 0x001902, 0x4CFF88, 0x00FF55, 0x00591D, 0x001F12,
+    0x4CFFB7, 0x00FF99, 0x005935, 0x001912, 0x4CC3FF, 0x00A9FF, 0x004152, 0x001019,
+    0x4C88FF, 0x0055FF, 0x001D59, 0x000819, 0x4C4CFF, 0x0000FF, 0x000059, 0x000019,
+    0x874CFF, 0x5400FF, 0x190064, 0x0F0030, 0xFF4CFF, 0xFF00FF, 0x590059, 0x190019,
+    0xFF4C87, 0xFF0054, 0x59001D, 0x220013, 0xFF1500, 0x993500, 0x795100, 0x436400,
+];
+
+/// What a pad or button shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Light {
+    Off,
+    /// A clip or scene with this color, not playing
+    Stopped(u32),
+    Triggered(u32),
+    Playing,
+    Recording,
+}
+
+/// The MIDI messages that light every pad and button mapped to a clip or scene,
+/// including those to turn off. No session lights nothing.
+pub fn leds<'a>(
+    mappings: impl IntoIterator<Item = &'a MidiMapping>,
+    mode: FeedbackMode,
+    session_box: SessionBox,
+    session: Option<&SessionState>,
+) -> HashMap<Trigger, Vec<[u8; 3]>> {
+    mappings
+        .into_iter()
+        .filter(|mapping| matches!(mapping.action, MidiAction::ClipFire { .. } | MidiAction::SceneFire { .. }))
+        .map(|mapping| {
+            let light = session.map_or(Light::Off, |session| light(&mapping.action, session_box, session));
+            (mapping.trigger, messages(&mapping.trigger, light, mode))
+        })
+        .collect()
+}
+
+fn light(action: &MidiAction, session_box: SessionBox, session: &SessionState) -> Light {
+    match *action {
+        MidiAction::ClipFire { track, scene } => {
+            let clip = session_box
+                .track_index(track, session)
+                .zip(session_box.scene_index(scene, session))
+                .and_then(|(track, scene)| session.tracks[track].clips.get(scene)?.clip.as_ref());
+            match clip {
+                None => Light::Off,
+                Some(clip) if clip.is_recording => Light::Recording,
+                Some(clip) if clip.is_triggered => Light::Triggered(clip.color),
+                Some(clip) if clip.is_playing => Light::Playing,
+                Some(clip) => Light::Stopped(clip.color),
+            }
+        }
+        MidiAction::SceneFire { scene } => match session_box.scene_index(scene, session) {
+            None => Light::Off,
+            Some(index) => {
+                let color = session.scenes[index].color;
+                match scene_menu::scene_state(session, index) {
+                    SceneState::Triggered => Light::Triggered(color),
+                    SceneState::Playing => Light::Playing,
+                    SceneState::Stopped => Light::Stopped(color),
+                }
+            }
+        },
+        _ => Light::Off,
+    }
+}
+
+fn messages(trigger: &Trigger, light: Light, mode: FeedbackMode) -> Vec<[u8; 3]> {
+    let status = match trigger.kind {
+        TriggerKind::Note => 0x90,
+        TriggerKind::Cc => 0xB0,
+    };
+    let message = |channel: u8, value: u8| [status | channel, trigger.number, value];
+
+    match mode {
+        FeedbackMode::Launchpad => match light {
+            Light::Off => vec![message(SOLID, 0)],
+            Light::Stopped(color) => vec![message(SOLID, palette_index(color))],
+            Light::Triggered(color) => vec![message(SOLID, palette_index(color)), message(FLASHING, GREEN)],
+            Light::Playing => vec![message(PULSING, GREEN)],
+            Light::Recording => vec![message(PULSING, RED)],
+        },
+        FeedbackMode::Generic => {
+            let velocity = match light {
+                Light::Off => 0,
+                Light::Stopped(_) => GENERIC_STOPPED,
+                Light::Triggered(_) => GENERIC_TRIGGERED,
+                Light::Playing => GENERIC_PLAYING,
+                Light::Recording => GENERIC_RECORDING,
+            };
+            vec![message(trigger.channel.saturating_sub(1), velocity)]
+        }
+    }
+}
+
+/// The nearest Launchpad palette entry to an RGB color. Black is dim white
+/// rather than 0 (off), so clips and scenes without a color still show.
+pub fn palette_index(color: u32) -> u8 {
+    let rgb = |color: u32| [(color >> 16) & 0xFF, (color >> 8) & 0xFF, color & 0xFF].map(|c| c as i32);
+    let target = rgb(color);
+    let distance = |entry: u32| {
+        rgb(entry)
+            .iter()
+            .zip(target)
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<i32>()
+    };
+
+    (0..LAUNCHPAD_PALETTE.len())
+        .min_by_key(|&index| distance(LAUNCHPAD_PALETTE[index]))
+        .unwrap_or(0)
+        .max(1) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mission_control_protocol::{Clip, ClipSlot, Scene, Track};
+
+    const BLUE: u32 = 0x0000FF;
+    /// Palette entry for `BLUE`
+    const BLUE_INDEX: u8 = 45;
+
+    fn clip(change: impl FnOnce(&mut Clip)) -> ClipSlot {
+        let mut clip = Clip { color: BLUE, ..Clip::default() };
+        change(&mut clip);
+        ClipSlot { has_clip: true, clip: Some(clip), ..ClipSlot::default() }
+    }
+
+    /// One track with a clip in each state, top to bottom: stopped, triggered,
+    /// playing, recording and an empty slot. A second track is empty.
+    fn session() -> SessionState {
+        let clips = vec![
+            clip(|_| {}),
+            clip(|clip| clip.is_triggered = true),
+            clip(|clip| clip.is_playing = true),
+            clip(|clip| clip.is_recording = true),
+            ClipSlot::default(),
+        ];
+        SessionState {
+            tracks: vec![
+                Track { id: 10, clips, ..Track::default() },
+                Track { id: 11, clips: vec![ClipSlot::default(); 5], ..Track::default() },
+            ],
+            scenes: (0..5).map(|id| Scene { id, color: BLUE, ..Scene::default() }).collect(),
+            ..SessionState::default()
+        }
+    }
+
+    /// A pad on note `number`, channel 1, firing track 1's clip in `scene`
+    fn pad(number: u8, scene: u32) -> MidiMapping {
+        MidiMapping {
+            trigger: Trigger { kind: TriggerKind::Note, channel: 1, number },
+            action: MidiAction::ClipFire { track: 1, scene },
+        }
+    }
+
+    fn lit(mapping: &MidiMapping, mode: FeedbackMode, session_box: SessionBox) -> Vec<[u8; 3]> {
+        leds([mapping], mode, session_box, Some(&session())).remove(&mapping.trigger).unwrap()
+    }
+
+    #[test]
+    fn lights_each_clip_state_on_a_launchpad() {
+        let mode = FeedbackMode::Launchpad;
+        let origin = SessionBox::default();
+        assert_eq!(lit(&pad(81, 1), mode, origin), [[0x90, 81, BLUE_INDEX]]);
+        assert_eq!(lit(&pad(71, 2), mode, origin), [[0x90, 71, BLUE_INDEX], [0x91, 71, GREEN]]);
+        assert_eq!(lit(&pad(61, 3), mode, origin), [[0x92, 61, GREEN]]);
+        assert_eq!(lit(&pad(51, 4), mode, origin), [[0x92, 51, RED]]);
+        assert_eq!(lit(&pad(41, 5), mode, origin), [[0x90, 41, 0]]);
+        // Past the last scene
+        assert_eq!(lit(&pad(31, 6), mode, origin), [[0x90, 31, 0]]);
+    }
+
+    #[test]
+    fn lights_each_clip_state_with_generic_velocities() {
+        let mode = FeedbackMode::Generic;
+        let origin = SessionBox::default();
+        assert_eq!(lit(&pad(36, 1), mode, origin), [[0x90, 36, GENERIC_STOPPED]]);
+        assert_eq!(lit(&pad(37, 2), mode, origin), [[0x90, 37, GENERIC_TRIGGERED]]);
+        assert_eq!(lit(&pad(38, 3), mode, origin), [[0x90, 38, GENERIC_PLAYING]]);
+        assert_eq!(lit(&pad(39, 4), mode, origin), [[0x90, 39, GENERIC_RECORDING]]);
+        assert_eq!(lit(&pad(40, 5), mode, origin), [[0x90, 40, 0]]);
+
+        // On the mapping's own channel
+        let mut on_channel_10 = pad(36, 3);
+        on_channel_10.trigger.channel = 10;
+        assert_eq!(lit(&on_channel_10, mode, origin), [[0x99, 36, GENERIC_PLAYING]]);
+    }
+
+    #[test]
+    fn follows_the_session_box() {
+        let mode = FeedbackMode::Generic;
+        // Scene 1 of the box is the session's third scene, where the clip plays
+        assert_eq!(lit(&pad(36, 1), mode, SessionBox { track: 0, scene: 2 }), [[0x90, 36, GENERIC_PLAYING]]);
+        // Track 1 of the box is the empty second track
+        assert_eq!(lit(&pad(36, 3), mode, SessionBox { track: 1, scene: 0 }), [[0x90, 36, 0]]);
+    }
+
+    #[test]
+    fn lights_scene_buttons() {
+        let button = |scene| MidiMapping {
+            trigger: Trigger { kind: TriggerKind::Cc, channel: 1, number: 89 },
+            action: MidiAction::SceneFire { scene },
+        };
+        let mode = FeedbackMode::Launchpad;
+        let origin = SessionBox::default();
+        assert_eq!(lit(&button(1), mode, origin), [[0xB0, 89, BLUE_INDEX]]);
+        assert_eq!(lit(&button(2), mode, origin), [[0xB0, 89, BLUE_INDEX], [0xB1, 89, GREEN]]);
+        // A scene plays when every clip in it does; the second track has none
+        assert_eq!(lit(&button(3), mode, origin), [[0xB2, 89, GREEN]]);
+        assert_eq!(lit(&button(9), mode, origin), [[0xB0, 89, 0]]);
+    }
+
+    #[test]
+    fn turns_everything_off_without_a_session() {
+        let leds = leds([&pad(81, 1), &pad(82, 2)], FeedbackMode::Launchpad, SessionBox::default(), None);
+        assert_eq!(leds.len(), 2);
+        assert!(leds.values().all(|messages| messages.iter().all(|message| message[2] == 0)));
+    }
+
+    #[test]
+    fn ignores_transport_mappings() {
+        let play = MidiMapping {
+            trigger: Trigger { kind: TriggerKind::Cc, channel: 1, number: 20 },
+            action: MidiAction::Play,
+        };
+        assert!(leds([&play], FeedbackMode::Launchpad, SessionBox::default(), Some(&session())).is_empty());
+    }
+
+    #[test]
+    fn matches_palette_colors() {
+        assert_eq!(palette_index(0xFF0000), RED);
+        assert_eq!(palette_index(0x00FF00), GREEN);
+        assert_eq!(palette_index(BLUE), BLUE_INDEX);
+        assert_eq!(palette_index(0xFFFFFF), 3);
+    }
+
+    #[test]
+    fn never_turns_a_clip_off() {
+        assert_ne!(palette_index(0), 0);
+        assert_ne!(palette_index(0x010101), 0);
+        assert!((0..=0xFF).all(|gray| palette_index(gray * 0x010101) != 0));
+    }
+}
@@ -0,0 +1,453 @@
+//! MIDI mapping profiles: which notes and CCs do what
+//!
+//! Kept apart from the MIDI ports (see `midi`) so settings can use the types
+//! without opening any.
+
+use mission_control_protocol::{ClientMessage, SessionState};
+use serde::{Deserialize, Serialize};
+
+use crate::settings::FieldError;
+
+/// Profile for a Launchpad in Programmer mode, the default
+pub const LAUNCHPAD_PROFILE: &str = "Launchpad";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MidiSettings {
+    /// Names of the input ports to listen on
+    pub inputs: Vec<String>,
+    /// Also create a "Mission Control" input other apps can connect to
+    /// (not on Windows). Handy for testing with ALSA's `aconnect` and `amidi`.
+    pub virtual_input: bool,
+    /// Name of the profile in use
+    pub profile: String,
+    pub profiles: Vec<MidiProfile>,
+    /// Name of the output port to light the controller's pads through
+    pub output: Option<String>,
+    /// Also create a "Mission Control" output other apps can read the LED
+    /// messages from (not on Windows)
+    pub virtual_output: bool,
+    pub feedback: FeedbackMode,
+}
+
+/// How pads are lit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedbackMode {
+    /// Clip colors from the Launchpad palette, flashing and pulsing
+    #[default]
+    Launchpad,
+    /// A fixed velocity for each clip state
+    Generic,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct MidiProfile {
+    pub name: String,
+    pub mappings: Vec<MidiMapping>,
+}
+
+/// What a note or CC does, e.g.
+/// `{ "trigger": { "type": "note", "channel": 1, "number": 11 }, "action": "clipFire", "track": 1, "scene": 8 }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MidiMapping {
+    pub trigger: Trigger,
+    #[serde(flatten)]
+    pub action: MidiAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Trigger {
+    #[serde(rename = "type")]
+    pub kind: TriggerKind,
+    /// 1 to 16
+    pub channel: u8,
+    /// Note or controller number, 0 to 127
+    pub number: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TriggerKind {
+    Note,
+    Cc,
+}
+
+/// Tracks and scenes are numbered from 1, from the top left of the session box
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum MidiAction {
+    /// Fire the clip at this grid position
+    ClipFire { track: u32, scene: u32 },
+    SceneFire { scene: u32 },
+    /// Set the track's volume from a CC's value
+    Volume { track: u32 },
+    Play,
+    Stop,
+    /// Play, or stop if playing
+    PlayStop,
+    /// The rest toggle
+    Record,
+    Metronome,
+    Loop,
+    TapTempo,
+    /// Move the session box a track or scene
+    SessionBoxUp,
+    SessionBoxDown,
+    SessionBoxLeft,
+    SessionBoxRight,
+}
+
+/// The part of the session a controller's grid shows and fires, like the red
+/// ring Live draws for control surfaces. Offsets are from the first track and
+/// scene.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionBox {
+    pub track: u32,
+    pub scene: u32,
+}
+
+impl Default for MidiSettings {
+    fn default() -> Self {
+        MidiSettings {
+            inputs: Vec::new(),
+            virtual_input: false,
+            profile: LAUNCHPAD_PROFILE.to_string(),
+            profiles: vec![launchpad_profile()],
+            output: None,
+            virtual_output: false,
+            feedback: FeedbackMode::default(),
+        }
+    }
+}
+
+impl MidiSettings {
+    /// The profile in use, if it exists
+    pub fn active_profile(&self) -> Option<&MidiProfile> {
+        self.profiles.iter().find(|profile| profile.name == self.profile)
+    }
+
+    /// Check names are unique and no profile maps a trigger twice. Errors are
+    /// for `midi.profiles.<index>.name` and `midi.profiles.<index>.mappings.<index>`.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        for (index, profile) in self.profiles.iter().enumerate() {
+            let name_error = |message: &str| FieldError {
+                field: format!("midi.profiles.{}.name", index).into(),
+                message: message.to_string(),
+            };
+            if profile.name.trim().is_empty() {
+                errors.push(name_error("Enter a name for this profile."));
+            } else if self.profiles[..index].iter().any(|other| other.name == profile.name) {
+                errors.push(name_error("Another profile has this name."));
+            }
+
+            for (mapping_index, mapping) in profile.mappings.iter().enumerate() {
+                let trigger = &mapping.trigger;
+                let message = if !(1..=16).contains(&trigger.channel) || trigger.number > 127 {
+                    "Learn a note or CC for this mapping."
+                } else if mapping.action.numbers().contains(&0) {
+                    "Tracks and scenes are numbered from 1."
+                } else if profile.mappings[..mapping_index].iter().any(|other| other.trigger == *trigger) {
+                    "Another mapping in this profile uses this note or CC."
+                } else {
+                    continue;
+                };
+                errors.push(FieldError {
+                    field: format!("midi.profiles.{}.mappings.{}", index, mapping_index).into(),
+                    message: message.to_string(),
+                });
+            }
+        }
+        errors
+    }
+}
+
+impl MidiAction {
+    /// The track and scene numbers in the action
+    fn numbers(&self) -> Vec<u32> {
+        match *self {
+            MidiAction::ClipFire { track, scene } => vec![track, scene],
+            MidiAction::SceneFire { scene } => vec![scene],
+            MidiAction::Volume { track } => vec![track],
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl SessionBox {
+    /// The box moved by `action`, kept within the session's tracks and scenes.
+    /// `None` if `action` doesn't move it.
+    pub fn moved(self, action: &MidiAction, session: &SessionState) -> Option<SessionBox> {
+        let last_track = session.tracks.len().saturating_sub(1) as u32;
+        let last_scene = session.scenes.len().saturating_sub(1) as u32;
+        let (track, scene) = match action {
+            MidiAction::SessionBoxUp => (self.track, self.scene.saturating_sub(1)),
+            MidiAction::SessionBoxDown => (self.track, self.scene + 1),
+            MidiAction::SessionBoxLeft => (self.track.saturating_sub(1), self.scene),
+            MidiAction::SessionBoxRight => (self.track + 1, self.scene),
+            _ => return None,
+        };
+        Some(SessionBox {
+            track: track.min(last_track),
+            scene: scene.min(last_scene),
+        })
+    }
+
+    /// Index of track `number` in the box (from 1), if it's in the session
+    pub fn track_index(self, number: u32, session: &SessionState) -> Option<usize> {
+        let index = (self.track + number.checked_sub(1)?) as usize;
+        (index < session.tracks.len()).then_some(index)
+    }
+
+    /// Index of scene `number` in the box (from 1), if it's in the session
+    pub fn scene_index(self, number: u32, session: &SessionState) -> Option<usize> {
+        let index = (self.scene + number.checked_sub(1)?) as usize;
+        (index < session.scenes.len()).then_some(index)
+    }
+}
+
+/// A Launchpad's pads and buttons in Programmer mode. Pads send notes
+/// `row * 10 + column`, counting rows from the bottom; the scene buttons on the
+/// right are CCs 89, 79 and so on down to 19, and the arrows are CCs 91 to 94.
+pub fn launchpad_profile() -> MidiProfile {
+    let mut mappings = Vec::new();
+    for scene in 0..8u8 {
+        let row = 8 - scene;
+        for track in 0..8u8 {
+            mappings.push(MidiMapping {
+                trigger: Trigger { kind: TriggerKind::Note, channel: 1, number: row * 10 + track + 1 },
+                action: MidiAction::ClipFire { track: (track + 1).into(), scene: (scene + 1).into() },
+            });
+        }
+        mappings.push(MidiMapping {
+            trigger: Trigger { kind: TriggerKind::Cc, channel: 1, number: row * 10 + 9 },
+            action: MidiAction::SceneFire { scene: (scene + 1).into() },
+        });
+    }
+    let arrows = [
+        MidiAction::SessionBoxUp,
+        MidiAction::SessionBoxDown,
+        MidiAction::SessionBoxLeft,
+        MidiAction::SessionBoxRight,
+    ];
+    for (number, action) in (91..).zip(arrows) {
+        mappings.push(MidiMapping {
+            trigger: Trigger { kind: TriggerKind::Cc, channel: 1, number },
+            action,
+        });
+    }
+    MidiProfile { name: LAUNCHPAD_PROFILE.to_string(), mappings }
+}
+
+/// The trigger and value of a note-on or CC message. Note-offs (including
+/// note-ons with velocity 0) and everything else are `None`.
+pub fn parse(bytes: &[u8]) -> Option<(Trigger, u8)> {
+    let [status, number, value] = *bytes else { return None };
+    let channel = (status & 0x0F) + 1;
+    let kind = match status & 0xF0 {
+        0x90 if value > 0 => TriggerKind::Note,
+        0xB0 => TriggerKind::Cc,
+        _ => return None,
+    };
+    Some((Trigger { kind, channel, number }, value))
+}
+
+/// What a trigger with `value` sends for `action`, given the session and where
+/// the session box is. Buttons act when pressed, not when released.
+pub fn messages(action: &MidiAction, value: u8, session: &SessionState, session_box: SessionBox) -> Vec<ClientMessage> {
+    if value == 0 && !matches!(action, MidiAction::Volume { .. }) {
+        return Vec::new();
+    }
+
+    let track_id = |track: &u32| Some(session.tracks[session_box.track_index(*track, session)?].id);
+    let scene_id = |scene: &u32| Some(session.scenes[session_box.scene_index(*scene, session)?].id);
+
+    let message = match action {
+        MidiAction::ClipFire { track, scene } => track_id(track)
+            .zip(scene_id(scene))
+            .map(|(track_id, scene_id)| ClientMessage::ClipFire { track_id, scene_id }),
+        MidiAction::SceneFire { scene } => scene_id(scene).map(|scene_id| ClientMessage::SceneFire { scene_id }),
+        MidiAction::Volume { track } => track_id(track).map(|track_id| ClientMessage::MixerVolume {
+            track_id,
+            value: f64::from(value) / 127.0,
+        }),
+        MidiAction::Play => Some(ClientMessage::TransportPlay),
+        MidiAction::Stop => Some(ClientMessage::TransportStop),
+        MidiAction::PlayStop if session.is_playing => Some(ClientMessage::TransportStop),
+        MidiAction::PlayStop => Some(ClientMessage::TransportPlay),
+        MidiAction::Record => Some(ClientMessage::TransportRecord { enabled: !session.is_recording }),
+        MidiAction::Metronome => Some(ClientMessage::TransportMetronome { enabled: !session.metronome }),
+        MidiAction::Loop => Some(ClientMessage::TransportLoop { enabled: !session.r#loop }),
+        MidiAction::TapTempo => Some(ClientMessage::TransportTapTempo),
+        // Moving the box doesn't send anything
+        MidiAction::SessionBoxUp | MidiAction::SessionBoxDown | MidiAction::SessionBoxLeft | MidiAction::SessionBoxRight => None,
+    };
+    message.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mission_control_protocol::{Scene, Track};
+
+    /// A session with ids that differ from indexes: tracks from 100, scenes from 200
+    fn session(tracks: i32, scenes: i32) -> SessionState {
+        SessionState {
+            tracks: (0..tracks).map(|index| Track { id: 100 + index, ..Track::default() }).collect(),
+            scenes: (0..scenes).map(|index| Scene { id: 200 + index, ..Scene::default() }).collect(),
+            ..SessionState::default()
+        }
+    }
+
+    fn note(channel: u8, number: u8) -> Trigger {
+        Trigger { kind: TriggerKind::Note, channel, number }
+    }
+
+    fn cc(channel: u8, number: u8) -> Trigger {
+        Trigger { kind: TriggerKind::Cc, channel, number }
+    }
+
+    #[test]
+    fn parses_notes_and_ccs() {
+        assert_eq!(parse(&[0x90, 60, 100]), Some((note(1, 60), 100)));
+        assert_eq!(parse(&[0xB0, 7, 64]), Some((cc(1, 7), 64)));
+        // A released button's CC still counts; `messages` ignores it
+        assert_eq!(parse(&[0xB0, 91, 0]), Some((cc(1, 91), 0)));
+    }
+
+    #[test]
+    fn numbers_channels_from_1() {
+        assert_eq!(parse(&[0x93, 36, 1]), Some((note(4, 36), 1)));
+        assert_eq!(parse(&[0xBF, 1, 127]), Some((cc(16, 1), 127)));
+    }
+
+    #[test]
+    fn ignores_note_offs_and_other_messages() {
+        assert_eq!(parse(&[0x90, 60, 0]), None);
+        assert_eq!(parse(&[0x80, 60, 64]), None);
+        assert_eq!(parse(&[0xE0, 0, 64]), None);
+        assert_eq!(parse(&[0xC0, 5]), None);
+        assert_eq!(parse(&[0xF8]), None);
+        assert_eq!(parse(&[]), None);
+    }
+
+    #[test]
+    fn fires_clips_and_scenes_from_the_session_box() {
+        let session = session(10, 10);
+        let origin = SessionBox::default();
+        let moved = SessionBox { track: 2, scene: 3 };
+
+        let clip = MidiAction::ClipFire { track: 1, scene: 2 };
+        assert_eq!(messages(&clip, 127, &session, origin), [ClientMessage::ClipFire { track_id: 100, scene_id: 201 }]);
+        assert_eq!(messages(&clip, 127, &session, moved), [ClientMessage::ClipFire { track_id: 102, scene_id: 204 }]);
+        assert_eq!(messages(&MidiAction::SceneFire { scene: 1 }, 1, &session, moved), [ClientMessage::SceneFire { scene_id: 203 }]);
+        // Off the end of the session
+        assert_eq!(messages(&MidiAction::ClipFire { track: 8, scene: 8 }, 127, &session, SessionBox { track: 5, scene: 0 }), []);
+        assert_eq!(messages(&MidiAction::SceneFire { scene: 0 }, 127, &session, origin), []);
+    }
+
+    #[test]
+    fn acts_when_pressed_not_released() {
+        let session = session(2, 2);
+        let origin = SessionBox::default();
+        assert_eq!(messages(&MidiAction::ClipFire { track: 1, scene: 1 }, 0, &session, origin), []);
+        assert_eq!(messages(&MidiAction::Play, 0, &session, origin), []);
+        assert_eq!(messages(&MidiAction::Play, 64, &session, origin), [ClientMessage::TransportPlay]);
+    }
+
+    #[test]
+    fn sets_volume_from_the_whole_cc_range() {
+        let session = session(2, 2);
+        let volume = |value| messages(&MidiAction::Volume { track: 2 }, value, &session, SessionBox::default());
+        assert_eq!(volume(0), [ClientMessage::MixerVolume { track_id: 101, value: 0.0 }]);
+        assert_eq!(volume(127), [ClientMessage::MixerVolume { track_id: 101, value: 1.0 }]);
+    }
+
+    #[test]
+    fn toggles_from_the_session() {
+        let mut session = session(1, 1);
+        let origin = SessionBox::default();
+        assert_eq!(messages(&MidiAction::PlayStop, 127, &session, origin), [ClientMessage::TransportPlay]);
+        assert_eq!(messages(&MidiAction::Metronome, 127, &session, origin), [ClientMessage::TransportMetronome { enabled: true }]);
+        session.is_playing = true;
+        session.is_recording = true;
+        assert_eq!(messages(&MidiAction::PlayStop, 127, &session, origin), [ClientMessage::TransportStop]);
+        assert_eq!(messages(&MidiAction::Record, 127, &session, origin), [ClientMessage::TransportRecord { enabled: false }]);
+        assert_eq!(messages(&MidiAction::SessionBoxDown, 127, &session, origin), []);
+    }
+
+    #[test]
+    fn keeps_the_session_box_in_the_session() {
+        let session = session(3, 2);
+        let origin = SessionBox::default();
+        assert_eq!(origin.moved(&MidiAction::SessionBoxUp, &session), Some(origin));
+        assert_eq!(origin.moved(&MidiAction::SessionBoxLeft, &session), Some(origin));
+        assert_eq!(origin.moved(&MidiAction::SessionBoxRight, &session), Some(SessionBox { track: 1, scene: 0 }));
+        assert_eq!(origin.moved(&MidiAction::SessionBoxDown, &session), Some(SessionBox { track: 0, scene: 1 }));
+
+        let corner = SessionBox { track: 2, scene: 1 };
+        assert_eq!(corner.moved(&MidiAction::SessionBoxRight, &session), Some(corner));
+        assert_eq!(corner.moved(&MidiAction::SessionBoxDown, &session), Some(corner));
+        assert_eq!(corner.moved(&MidiAction::Play, &session), None);
+
+        // A session that shrank pulls the box back in
+        assert_eq!(SessionBox { track: 9, scene: 9 }.moved(&MidiAction::SessionBoxUp, &session), Some(SessionBox { track: 2, scene: 1 }));
+        assert_eq!(corner.moved(&MidiAction::SessionBoxLeft, &SessionState::default()), Some(origin));
+    }
+
+    #[test]
+    fn maps_the_launchpad_grid() {
+        let profile = launchpad_profile();
+        let action = |trigger: Trigger| {
+            profile.mappings.iter().find(|mapping| mapping.trigger == trigger).map(|mapping| mapping.action.clone())
+        };
+
+        assert_eq!(profile.mappings.len(), 8 * 8 + 8 + 4);
+        // Rows count from the bottom, scenes from the top
+        assert_eq!(action(note(1, 81)), Some(MidiAction::ClipFire { track: 1, scene: 1 }));
+        assert_eq!(action(note(1, 88)), Some(MidiAction::ClipFire { track: 8, scene: 1 }));
+        assert_eq!(action(note(1, 11)), Some(MidiAction::ClipFire { track: 1, scene: 8 }));
+        assert_eq!(action(note(1, 18)), Some(MidiAction::ClipFire { track: 8, scene: 8 }));
+        assert_eq!(action(cc(1, 89)), Some(MidiAction::SceneFire { scene: 1 }));
+        assert_eq!(action(cc(1, 19)), Some(MidiAction::SceneFire { scene: 8 }));
+        assert_eq!(action(cc(1, 91)), Some(MidiAction::SessionBoxUp));
+        assert_eq!(action(cc(1, 94)), Some(MidiAction::SessionBoxRight));
+        assert_eq!(action(note(1, 19)), None);
+        assert_eq!(action(note(1, 91)), None);
+
+        assert_eq!(MidiSettings::default().validate(), []);
+    }
+
+    #[test]
+    fn validates_profiles() {
+        let mapping = |trigger, action| MidiMapping { trigger, action };
+        let mut settings = MidiSettings::default();
+        settings.profiles.push(MidiProfile {
+            name: LAUNCHPAD_PROFILE.to_string(),
+            mappings: vec![
+                mapping(note(0, 1), MidiAction::Play),
+                mapping(note(1, 1), MidiAction::SceneFire { scene: 0 }),
+                mapping(note(1, 2), MidiAction::Stop),
+                mapping(note(1, 2), MidiAction::Loop),
+            ],
+        });
+        settings.profiles.push(MidiProfile { name: " ".to_string(), mappings: Vec::new() });
+
+        let errors: Vec<_> = settings.validate().into_iter().map(|error| (error.field.into_owned(), error.message)).collect();
+        assert_eq!(
+            errors,
+            [
+                ("midi.profiles.1.name".to_string(), "Another profile has this name.".to_string()),
+                ("midi.profiles.1.mappings.0".to_string(), "Learn a note or CC for this mapping.".to_string()),
+                ("midi.profiles.1.mappings.1".to_string(), "Tracks and scenes are numbered from 1.".to_string()),
+                ("midi.profiles.1.mappings.3".to_string(), "Another mapping in this profile uses this note or CC.".to_string()),
+                ("midi.profiles.2.name".to_string(), "Enter a name for this profile.".to_string()),
+            ]
+        );
+    }
+}
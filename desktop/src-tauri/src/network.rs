@@ -0,0 +1,49 @@
+//! This machine's addresses, for telling other devices where to connect
+
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+
+/// Get the local network IP address (of the interface with the default route)
+pub fn local_ip() -> Option<Ipv4Addr> {
+    // Create a UDP socket and "connect" to a public IP to determine local interface
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) => Some(ip),
+        IpAddr::V6(_) => None,
+    }
+}
+
+/// IPv4 addresses other devices might reach us on, the default route's first
+pub fn lan_addresses() -> Vec<Ipv4Addr> {
+    let mut addresses: Vec<Ipv4Addr> = local_ip().into_iter().collect();
+
+    for interface in if_addrs::get_if_addrs().unwrap_or_default() {
+        if interface.is_loopback() || interface.is_link_local() {
+            continue;
+        }
+        if let IpAddr::V4(ip) = interface.ip() {
+            if !addresses.contains(&ip) {
+                addresses.push(ip);
+            }
+        }
+    }
+    addresses
+}
+
+/// URLs for the UI on `port`: one per LAN address, then localhost
+pub fn ui_urls(port: u16) -> Vec<String> {
+    lan_addresses()
+        .into_iter()
+        .map(|ip| format!("http://{}:{}", ip, port))
+        .chain([format!("http://localhost:{}", port)])
+        .collect()
+}
+
+/// Every interface address, e.g. "en0: 192.168.1.20", for diagnostics
+pub fn interfaces() -> Vec<String> {
+    if_addrs::get_if_addrs()
+        .unwrap_or_default()
+        .iter()
+        .map(|interface| format!("{}: {}", interface.name, interface.ip()))
+        .collect()
+}
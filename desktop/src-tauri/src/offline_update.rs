@@ -0,0 +1,225 @@
+//! Install an update from a bundle on disk, for machines without internet access
+//!
+//! The updater only installs from an endpoint, so the bundle is served to it from
+//! a one-off loopback server. It checks the signature again while "downloading".
+//!
+//! Release builds only allow plain HTTP endpoints because tauri.conf.json sets
+//! `dangerousInsecureTransportProtocol`; the updater has no per-check switch.
+//! [`updates::secure_endpoint`] keeps every other endpoint on HTTPS.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use semver::Version;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_updater::UpdaterExt;
+
+use crate::updates;
+
+/// Bundle formats produced by `createUpdaterArtifacts`
+const BUNDLE_EXTENSIONS: &[&str] = &["gz", "zip", "exe", "msi", "AppImage"];
+
+pub struct Bundle {
+    pub path: PathBuf,
+    pub bytes: Vec<u8>,
+    /// Base64, as it appears in `latest.json`
+    pub signature: String,
+    pub version: Option<Version>,
+}
+
+impl Bundle {
+    pub fn file_name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Ask for an update bundle, and for its signature unless `<bundle>.sig` is next
+/// to it. Returns `None` if the user cancels either picker.
+pub fn pick(app: &AppHandle) -> Result<Option<Bundle>, String> {
+    let Some(file) = app
+        .dialog()
+        .file()
+        .set_title("Choose Update Bundle")
+        .add_filter("Mission Control Update", BUNDLE_EXTENSIONS)
+        .blocking_pick_file()
+    else {
+        return Ok(None);
+    };
+    let path = file
+        .into_path()
+        .map_err(|e| format!("Could not open update bundle: {}", e))?;
+
+    let mut signature_path = path.clone().into_os_string();
+    signature_path.push(".sig");
+    let mut signature_path = PathBuf::from(signature_path);
+
+    if !signature_path.is_file() {
+        let mut picker = app
+            .dialog()
+            .file()
+            .set_title("Choose Update Signature (.sig)")
+            .add_filter("Signature", &["sig"]);
+        if let Some(dir) = path.parent() {
+            picker = picker.set_directory(dir);
+        }
+        let Some(file) = picker.blocking_pick_file() else {
+            return Ok(None);
+        };
+        signature_path = file
+            .into_path()
+            .map_err(|e| format!("Could not open signature: {}", e))?;
+    }
+
+    load(&path, &signature_path).map(Some)
+}
+
+/// Read a bundle and its signature file
+pub fn load(path: &Path, signature_path: &Path) -> Result<Bundle, String> {
+    let bytes = fs::read(path).map_err(|e| format!("Could not read update bundle: {}", e))?;
+    let signature = fs::read_to_string(signature_path)
+        .map_err(|e| format!("Could not read signature: {}", e))?;
+    let version = path
+        .file_name()
+        .and_then(|name| updates::version_from_file_name(&name.to_string_lossy()));
+
+    Ok(Bundle {
+        path: path.to_path_buf(),
+        bytes,
+        signature: updates::signature_base64(&signature),
+        version,
+    })
+}
+
+/// The updater public key from tauri.conf.json
+pub fn pubkey(app: &AppHandle) -> Result<String, String> {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|updater| updater.get("pubkey"))
+        .and_then(|pubkey| pubkey.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| "No updater public key configured".to_string())
+}
+
+/// Install a verified bundle. The caller restarts the app afterwards.
+pub async fn install(app: &AppHandle, bundle: Bundle) -> Result<(), String> {
+    let version = bundle
+        .version
+        .clone()
+        .unwrap_or_else(|| Version::new(0, 0, 0))
+        .to_string();
+    let server = LocalServer::start(bundle)?;
+
+    let manifest_url: tauri::Url = format!("http://{}/latest.json", server.addr)
+        .parse()
+        .map_err(|e| format!("Invalid manifest URL: {}", e))?;
+    updates::secure_endpoint(&manifest_url)?;
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![manifest_url])
+        .map_err(|e| format!("Could not configure updater: {}", e))?
+        // The user picked this bundle, so install it whatever its version
+        .version_comparator(|_, _| true)
+        .configure_client(|client| client.no_proxy())
+        .build()
+        .map_err(|e| format!("Could not configure updater: {}", e))?;
+
+    let update = updater
+        .check()
+        .await
+        .map_err(|e| format!("Could not read update bundle: {}", e))?
+        .ok_or("Update bundle was not offered")?;
+
+    log::info!("Installing update {} from file...", version);
+    let bytes = update
+        .download(|_, _| {}, || {})
+        .await
+        .map_err(|e| format!("Could not verify update bundle: {}", e))?;
+    update
+        .install(bytes)
+        .map_err(|e| format!("Could not install update: {}", e))
+}
+
+/// Serves `/latest.json` and `/bundle` on a random loopback port until dropped
+struct LocalServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+}
+
+impl LocalServer {
+    fn start(bundle: Bundle) -> Result<LocalServer, String> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .map_err(|e| format!("Could not start local update server: {}", e))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| format!("Could not start local update server: {}", e))?;
+
+        let manifest = serde_json::json!({
+            "version": bundle.version.as_ref().map_or("0.0.0".to_string(), |v| v.to_string()),
+            "url": format!("http://{}/bundle", addr),
+            "signature": bundle.signature,
+        })
+        .to_string();
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                if let Err(e) = respond(stream, &manifest, &bundle.bytes) {
+                    log::error!("[update] Local update server error: {}", e);
+                }
+            }
+        });
+
+        Ok(LocalServer { addr, stop })
+    }
+}
+
+impl Drop for LocalServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the blocking accept so the thread sees the flag
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+fn respond(mut stream: TcpStream, manifest: &str, bundle: &[u8]) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // Skip the headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let (status, content_type, body) = match request_line.split_whitespace().nth(1) {
+        Some("/latest.json") => ("200 OK", "application/json", manifest.as_bytes()),
+        Some("/bundle") => ("200 OK", "application/octet-stream", bundle),
+        _ => ("404 Not Found", "text/plain", &b"Not found"[..]),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    )?;
+    stream.write_all(body)?;
+    stream.flush()
+}
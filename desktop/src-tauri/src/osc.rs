@@ -0,0 +1,203 @@
+//! Minimal OSC client for talking to AbletonOSC directly, without the bridge
+
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use mission_control_protocol::control;
+use rosc::{OscMessage, OscPacket, OscType};
+use serde::Serialize;
+
+/// Port AbletonOSC listens on (matches `oscSendPort` in server/config.ts)
+pub const OSC_SEND_PORT: u16 = 11000;
+/// Port AbletonOSC replies to (matches `oscReceivePort` in server/config.ts)
+pub const OSC_RECEIVE_PORT: u16 = 11001;
+
+/// What a direct probe of AbletonOSC found
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Probe {
+    /// AbletonOSC answered `/live/test`
+    pub responding: bool,
+    /// Live's version from `/live/application/get/version`, e.g. "12.1"
+    pub live_version: Option<String>,
+}
+
+/// Probe AbletonOSC on `host` (usually this machine).
+///
+/// AbletonOSC always replies to `OSC_RECEIVE_PORT` on the sender's host, so this
+/// needs that port to be free. While the bridge is running it owns the port and
+/// this fails with `ErrorKind::AddrInUse`.
+pub fn probe(host: &str, timeout: Duration) -> io::Result<Probe> {
+    let target = resolve(host)?;
+    // Replies from another machine arrive on our LAN address, not loopback
+    let reply_ip = if target.ip().is_loopback() { Ipv4Addr::LOCALHOST } else { Ipv4Addr::UNSPECIFIED };
+    probe_at(target, SocketAddr::from((reply_ip, OSC_RECEIVE_PORT)), timeout)
+}
+
+/// Resolve the AbletonOSC address for `host`, preferring IPv4 since the bridge
+/// and AbletonOSC both use it
+pub fn resolve(host: &str) -> io::Result<SocketAddr> {
+    let addresses: Vec<SocketAddr> = (host, OSC_SEND_PORT).to_socket_addrs()?.collect();
+    addresses
+        .iter()
+        .find(|address| address.is_ipv4())
+        .or(addresses.first())
+        .copied()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("Could not resolve {}", host)))
+}
+
+/// Probe an AbletonOSC instance at `target`, listening for replies on `reply`
+pub fn probe_at(target: SocketAddr, reply: SocketAddr, timeout: Duration) -> io::Result<Probe> {
+    let replies = query(target, reply, &["/live/test", "/live/application/get/version"], timeout)?;
+
+    let mut probe = Probe::default();
+    for message in replies {
+        match message.addr.as_str() {
+            "/live/test" => probe.responding = true,
+            "/live/application/get/version" => {
+                // Any reply at all means the script is loaded
+                probe.responding = true;
+                probe.live_version = parse_version(&message.args);
+            }
+            _ => {}
+        }
+    }
+    Ok(probe)
+}
+
+/// Parse the `(major, minor)` reply to `/live/application/get/version`
+pub fn parse_version(args: &[OscType]) -> Option<String> {
+    let number = |arg: &OscType| match arg {
+        OscType::Int(n) => Some(*n as i64),
+        OscType::Long(n) => Some(*n),
+        OscType::Float(n) => Some(*n as i64),
+        _ => None,
+    };
+
+    match args {
+        [major, minor, ..] => Some(format!("{}.{}", number(major)?, number(minor)?)),
+        _ => None,
+    }
+}
+
+/// Send argument-less queries to `target` and collect the first reply to each,
+/// until every query has been answered or the timeout expires.
+pub fn query(
+    target: SocketAddr,
+    reply: SocketAddr,
+    addresses: &[&str],
+    timeout: Duration,
+) -> io::Result<Vec<OscMessage>> {
+    let socket = UdpSocket::bind(reply)?;
+    for address in addresses {
+        socket.send_to(&encode(address, vec![])?, target)?;
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut buf = [0u8; rosc::decoder::MTU];
+    let mut replies: Vec<OscMessage> = Vec::new();
+
+    while replies.len() < addresses.len() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(remaining))?;
+
+        let len = match socket.recv(&mut buf) {
+            Ok(len) => len,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => break,
+            // Windows reports ICMP port unreachable as a reset on the next recv
+            Err(e) if e.kind() == io::ErrorKind::ConnectionReset => continue,
+            Err(e) => return Err(e),
+        };
+
+        for message in decode(&buf[..len]) {
+            let wanted = addresses.contains(&message.addr.as_str());
+            if wanted && !replies.iter().any(|r| r.addr == message.addr) {
+                replies.push(message);
+            }
+        }
+    }
+
+    Ok(replies)
+}
+
+/// Encode a single OSC message
+pub fn encode(address: &str, args: Vec<OscType>) -> io::Result<Vec<u8>> {
+    rosc::encoder::encode(&OscPacket::Message(OscMessage {
+        addr: address.to_string(),
+        args,
+    }))
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))
+}
+
+/// Decode a datagram into its messages, flattening bundles. Malformed packets
+/// decode to nothing.
+pub fn decode(datagram: &[u8]) -> Vec<OscMessage> {
+    rosc::decoder::decode_udp(datagram)
+        .map(|(_, packet)| control::flatten(packet))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    /// A free loopback port for replies
+    fn reply_address() -> SocketAddr {
+        UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap()
+    }
+
+    /// Answers `/live/test` and the version query like AbletonOSC in Live 12.1
+    fn stand_in() -> SocketAddr {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buf = [0u8; rosc::decoder::MTU];
+            while let Ok((len, from)) = socket.recv_from(&mut buf) {
+                for message in decode(&buf[..len]) {
+                    let args = match message.addr.as_str() {
+                        "/live/test" => vec![OscType::String("ok".into())],
+                        "/live/application/get/version" => vec![OscType::Int(12), OscType::Int(1)],
+                        _ => continue,
+                    };
+                    socket.send_to(&encode(&message.addr, args).unwrap(), from).unwrap();
+                }
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn probes_a_responding_script() {
+        let probe = probe_at(stand_in(), reply_address(), Duration::from_secs(5)).unwrap();
+        assert_eq!(
+            probe,
+            Probe {
+                responding: true,
+                live_version: Some("12.1".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn times_out_without_a_script() {
+        // Bound but never answering
+        let silent = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let started = Instant::now();
+        let probe = probe_at(silent.local_addr().unwrap(), reply_address(), Duration::from_millis(200)).unwrap();
+        assert_eq!(probe, Probe::default());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn parses_live_versions() {
+        assert_eq!(parse_version(&[OscType::Int(12), OscType::Int(1)]), Some("12.1".into()));
+        assert_eq!(parse_version(&[OscType::Float(11.0), OscType::Long(3), OscType::Int(5)]), Some("11.3".into()));
+        assert_eq!(parse_version(&[OscType::Int(12)]), None);
+        assert_eq!(parse_version(&[OscType::String("12".into()), OscType::Int(1)]), None);
+    }
+}
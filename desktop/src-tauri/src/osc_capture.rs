@@ -0,0 +1,325 @@
+//! OSC capture: records every datagram between the bridge and AbletonOSC, and
+//! replays a recording against the bridge to reproduce a session without Live
+//!
+//! Recording happens in `osc_proxy`, which sits on both sides while capturing.
+//! A capture is JSON lines: a header, then one line per datagram with the
+//! seconds since capturing started, which way it went and its bytes in base64.
+//! The OSC address is there for reading; replay ignores it.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use mission_control_protocol::control;
+use rosc::OscPacket;
+use serde::{Deserialize, Serialize};
+
+use crate::osc::{OSC_RECEIVE_PORT, OSC_SEND_PORT};
+
+/// Bump when the format changes in a way older replays can't read
+pub const CAPTURE_VERSION: u32 = 1;
+
+/// First line of a capture
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Header {
+    version: u32,
+    /// UTC, e.g. "2026-10-18T14:03:27Z"
+    started: String,
+    osc_host: String,
+}
+
+/// Which way a datagram went
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    /// From the bridge to AbletonOSC
+    ToLive,
+    /// From AbletonOSC to the bridge
+    FromLive,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    /// Seconds since capturing started
+    time: f64,
+    direction: Direction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    address: Option<String>,
+    /// The datagram, in base64
+    data: String,
+}
+
+/// Writes a capture, one line per datagram
+pub struct Recorder {
+    file: BufWriter<File>,
+    started: Instant,
+}
+
+impl Recorder {
+    /// Start a capture in `dir`, named for the time it starts. Returns the
+    /// recorder and the file's path.
+    pub fn create(dir: &Path, osc_host: &str) -> Result<(Recorder, PathBuf), String> {
+        let now = utc(SystemTime::now());
+        // No colons, which Windows doesn't allow in file names
+        let path = dir.join(format!("osc-capture-{}.jsonl", now.replace(':', "-")));
+        let file = File::create(&path).map_err(|e| format!("Could not create {}: {}", path.display(), e))?;
+
+        let mut recorder = Recorder {
+            file: BufWriter::new(file),
+            started: Instant::now(),
+        };
+        let header = Header {
+            version: CAPTURE_VERSION,
+            started: now,
+            osc_host: osc_host.to_string(),
+        };
+        recorder
+            .write_line(&header)
+            .map_err(|e| format!("Could not write to {}: {}", path.display(), e))?;
+        Ok((recorder, path))
+    }
+
+    /// Add a datagram, flushing so a crash loses nothing before it
+    pub fn record(&mut self, direction: Direction, datagram: &[u8]) {
+        let address = match rosc::decoder::decode_udp(datagram) {
+            Ok((_, OscPacket::Message(message))) => Some(message.addr),
+            _ => None,
+        };
+        let record = Record {
+            time: self.started.elapsed().as_secs_f64(),
+            direction,
+            address,
+            data: BASE64.encode(datagram),
+        };
+        if let Err(e) = self.write_line(&record) {
+            log::warn!("[osc-capture] Could not record datagram: {}", e);
+        }
+    }
+
+    fn write_line(&mut self, value: &impl Serialize) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.file, value)?;
+        self.file.write_all(b"\n")?;
+        self.file.flush()
+    }
+}
+
+/// Replay what AbletonOSC sent in the capture at `path` to the bridge on this
+/// machine, at the pace it was sent. Takes AbletonOSC's port so Live mustn't be
+/// running, and starts once the bridge sends its first query there, lined up
+/// with the first query in the capture. Returns how many datagrams it sent.
+pub fn replay(path: &Path) -> Result<usize, String> {
+    let replies = replies(path)?;
+    let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, OSC_SEND_PORT)).map_err(|e| {
+        format!("Could not take AbletonOSC's port {} (quit Ableton Live first): {}", OSC_SEND_PORT, e)
+    })?;
+    // The bridge, or the app's proxy in front of it
+    let bridge = SocketAddr::from((Ipv4Addr::LOCALHOST, OSC_RECEIVE_PORT));
+    play(&socket, bridge, &replies)
+}
+
+/// What AbletonOSC sent in the capture at `path`, timed from the first query
+fn replies(path: &Path) -> Result<Vec<(f64, Vec<u8>)>, String> {
+    let (header, records) = load(path)?;
+    log::info!("[osc-capture] Replaying {} from {}", path.display(), header.started);
+    let first_query = records
+        .iter()
+        .find(|record| record.direction == Direction::ToLive)
+        .map_or(0.0, |record| record.time);
+    let replies: Vec<_> = records
+        .into_iter()
+        .filter(|record| record.direction == Direction::FromLive)
+        .map(|record| Ok((record.time - first_query, BASE64.decode(&record.data)?)))
+        .collect::<Result<_, base64::DecodeError>>()
+        .map_err(|e| format!("{} has a datagram that isn't base64: {}", path.display(), e))?;
+    if replies.is_empty() {
+        return Err(format!("{} has nothing from AbletonOSC to replay", path.display()));
+    }
+    Ok(replies)
+}
+
+/// Send `replies` from `socket` to `bridge` at their times, once the bridge
+/// sends its first query
+fn play(socket: &UdpSocket, bridge: SocketAddr, replies: &[(f64, Vec<u8>)]) -> Result<usize, String> {
+    let port = socket.local_addr().map_or(OSC_SEND_PORT, |address| address.port());
+    log::info!("[osc-capture] Waiting for the bridge to query AbletonOSC on port {}", port);
+    let mut buffer = vec![0; control::MAX_DATAGRAM];
+    socket
+        .recv_from(&mut buffer)
+        .map_err(|e| format!("Could not receive from the bridge: {}", e))?;
+    let started = Instant::now();
+    // The bridge keeps querying; nothing answers but the capture
+    let _ = socket.set_nonblocking(true);
+
+    for (index, (time, datagram)) in replies.iter().enumerate() {
+        let due = Duration::from_secs_f64(time.max(0.0));
+        if let Some(wait) = due.checked_sub(started.elapsed()) {
+            thread::sleep(wait);
+        }
+        socket
+            .send_to(datagram, bridge)
+            .map_err(|e| format!("Could not send datagram {} to the bridge: {}", index + 1, e))?;
+        while socket.recv_from(&mut buffer).is_ok() {}
+    }
+    Ok(replies.len())
+}
+
+fn load(path: &Path) -> Result<(Header, Vec<Record>), String> {
+    let file = File::open(path).map_err(|e| format!("Could not open {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+    let error = |line: usize, e: &dyn std::fmt::Display| format!("{} line {}: {}", path.display(), line, e);
+
+    let header: Header = match lines.next() {
+        Some(line) => {
+            let line = line.map_err(|e| error(1, &e))?;
+            serde_json::from_str(&line).map_err(|_| format!("{} isn't an OSC capture", path.display()))?
+        }
+        None => return Err(format!("{} is empty", path.display())),
+    };
+    if header.version > CAPTURE_VERSION {
+        return Err(format!("{} is from a newer version of Mission Control", path.display()));
+    }
+
+    let mut records = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| error(index + 2, &e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line).map_err(|e| error(index + 2, &e))?);
+    }
+    Ok((header, records))
+}
+
+/// UTC to the second, e.g. "2026-10-18T14:03:27Z"
+fn utc(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as i64;
+    let (days, seconds) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+    // Days since 1970-01-01 to a date, from Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rosc::{OscMessage, OscType};
+
+    fn datagram(addr: &str, args: Vec<OscType>) -> Vec<u8> {
+        rosc::encoder::encode(&OscPacket::Message(OscMessage { addr: addr.to_string(), args })).unwrap()
+    }
+
+    /// A capture of the bridge's first query and AbletonOSC's two replies
+    fn capture(dir: &Path) -> (PathBuf, Vec<Vec<u8>>) {
+        let query = datagram("/live/song/get/tempo", vec![]);
+        let replies = vec![
+            datagram("/live/song/get/tempo", vec![OscType::Float(120.0)]),
+            datagram("/live/song/get/is_playing", vec![OscType::Int(1)]),
+        ];
+        let (mut recorder, path) = Recorder::create(dir, "127.0.0.1").unwrap();
+        recorder.record(Direction::ToLive, &query);
+        for reply in &replies {
+            recorder.record(Direction::FromLive, reply);
+        }
+        (path, replies)
+    }
+
+    fn write(dir: &Path, lines: &[&str]) -> PathBuf {
+        let path = dir.join("capture.jsonl");
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    #[test]
+    fn loads_what_was_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, replies) = capture(dir.path());
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("osc-capture-"));
+
+        let (header, records) = load(&path).unwrap();
+        assert_eq!(header.version, CAPTURE_VERSION);
+        assert_eq!(header.osc_host, "127.0.0.1");
+        let directions: Vec<_> = records.iter().map(|record| record.direction).collect();
+        assert_eq!(directions, [Direction::ToLive, Direction::FromLive, Direction::FromLive]);
+        assert_eq!(records[2].address.as_deref(), Some("/live/song/get/is_playing"));
+        assert!(records.windows(2).all(|pair| pair[0].time <= pair[1].time));
+
+        let loaded: Vec<_> = super::replies(&path).unwrap().into_iter().map(|(_, datagram)| datagram).collect();
+        assert_eq!(loaded, replies);
+    }
+
+    #[test]
+    fn rejects_what_it_cant_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let header = |version| format!(r#"{{"version":{},"started":"2026-10-18T14:03:27Z","oscHost":"127.0.0.1"}}"#, version);
+        let (newer, header) = (header(CAPTURE_VERSION + 1), header(CAPTURE_VERSION));
+        let cases = [
+            (vec![newer.as_str()], "is from a newer version of Mission Control"),
+            (vec!["Not JSON"], "isn't an OSC capture"),
+            (vec![r#"{"name":"settings"}"#], "isn't an OSC capture"),
+            (vec![header.as_str(), r#"{"time":0.5,"direction":"fromLive","data":"not base64!"}"#], "has a datagram that isn't base64"),
+            (vec![header.as_str(), r#"{"time":0.5,"direction":"toLive","data":""}"#], "has nothing from AbletonOSC to replay"),
+        ];
+        for (lines, message) in cases {
+            let error = super::replies(&write(dir.path(), &lines)).unwrap_err();
+            assert!(error.contains(message), "{:?}: {}", lines, error);
+        }
+    }
+
+    #[test]
+    fn formats_utc() {
+        let utc = |seconds| utc(UNIX_EPOCH + Duration::from_secs(seconds));
+        assert_eq!(utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(utc(1792332207), "2026-10-18T14:03:27Z");
+        assert_eq!(utc(951782400), "2000-02-29T00:00:00Z");
+        assert_eq!(utc(1709251199), "2024-02-29T23:59:59Z");
+        assert_eq!(utc(1709251200), "2024-03-01T00:00:00Z");
+        assert_eq!(utc(4107542400), "2100-03-01T00:00:00Z");
+    }
+
+    #[test]
+    fn replays_replies_once_queried() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, replies) = capture(dir.path());
+        let live = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let live_address = live.local_addr().unwrap();
+        let bridge = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        bridge.set_read_timeout(Some(Duration::from_secs(2))).unwrap();
+        let bridge_address = bridge.local_addr().unwrap();
+
+        let player = thread::spawn(move || play(&live, bridge_address, &super::replies(&path).unwrap()));
+        bridge.send_to(&datagram("/live/song/get/tempo", vec![]), live_address).unwrap();
+
+        let mut buffer = [0; 1024];
+        for reply in &replies {
+            let (length, from) = bridge.recv_from(&mut buffer).unwrap();
+            assert_eq!(from, live_address);
+            assert_eq!(&buffer[..length], reply.as_slice());
+        }
+        assert_eq!(player.join().unwrap(), Ok(2));
+    }
+}
@@ -0,0 +1,124 @@
+//! OSC control: lets OSC controllers such as TouchOSC and Open Stage Control
+//! fire scenes and clips and drive the transport
+//!
+//! Listens on the network for the address space in
+//! `mission_control_protocol::control` and sends what it translates to through
+//! the bridge client, as MIDI and shortcuts do.
+
+use std::net::Ipv4Addr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use mission_control_protocol::control;
+use rosc::OscMessage;
+use serde::{Deserialize, Serialize};
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Manager};
+use tokio::net::UdpSocket;
+
+use crate::bridge_client;
+use crate::osc::{OSC_RECEIVE_PORT, OSC_SEND_PORT};
+use crate::osc_proxy::{BRIDGE_RECEIVE_PORT, BRIDGE_SEND_PORT};
+use crate::settings::FieldError;
+
+/// TouchOSC sends to this port by default
+pub const DEFAULT_PORT: u16 = 8000;
+
+/// How long to wait between attempts to take the port
+const RETRY: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct OscControlSettings {
+    pub enabled: bool,
+    /// UDP port to listen on, on every network interface
+    pub port: u16,
+}
+
+impl Default for OscControlSettings {
+    fn default() -> Self {
+        OscControlSettings {
+            enabled: false,
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+impl OscControlSettings {
+    /// Check the port, for `oscControl.port`
+    pub fn validate(&self) -> Vec<FieldError> {
+        let message = if self.port < 1024 {
+            "Use a port from 1024 to 65535. Lower ports need administrator rights.".to_string()
+        } else if [OSC_SEND_PORT, OSC_RECEIVE_PORT, BRIDGE_SEND_PORT, BRIDGE_RECEIVE_PORT].contains(&self.port) {
+            format!("Port {} is used by AbletonOSC and the bridge.", self.port)
+        } else {
+            return Vec::new();
+        };
+        vec![FieldError {
+            field: "oscControl.port".into(),
+            message,
+        }]
+    }
+}
+
+#[derive(Default)]
+pub struct OscControl {
+    /// The running server. Aborting it closes its socket.
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Listen for controllers as `settings` say, stopping any server already
+/// running
+pub fn start(app: &AppHandle, settings: &OscControlSettings) {
+    let state = app.state::<OscControl>();
+    if let Some(task) = state.task.lock().unwrap().take() {
+        task.abort();
+    }
+    if !settings.enabled {
+        return;
+    }
+
+    let port = settings.port;
+    let app = app.clone();
+    let task = tauri::async_runtime::spawn(async move {
+        // The last server may take a moment to let go of the port
+        let mut socket = None;
+        for _ in 0..10 {
+            match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, port)).await {
+                Ok(bound) => {
+                    socket = Some(bound);
+                    break;
+                }
+                Err(_) => tokio::time::sleep(RETRY).await,
+            }
+        }
+        let Some(socket) = socket else {
+            log::error!("[osc-control] Could not listen on port {}; is another app using it?", port);
+            return;
+        };
+        log::info!("[osc-control] Listening on port {}", port);
+
+        if let Err(e) = control::serve(socket, |message| run(&app, &message)).await {
+            log::error!("[osc-control] Could not receive: {}", e);
+        }
+    });
+    *state.task.lock().unwrap() = Some(task);
+}
+
+fn run(app: &AppHandle, message: &OscMessage) {
+    let messages = bridge_client::with_snapshot(app, |snapshot| control::translate(message, snapshot.live_session()))
+        .unwrap_or_else(|| Err("Not connected to the bridge".to_string()));
+    let messages = match messages {
+        Ok(messages) => messages,
+        Err(e) => {
+            log::warn!("[osc-control] {}", e);
+            return;
+        }
+    };
+    for message in messages {
+        if let Err(e) = bridge_client::send(app, message) {
+            log::warn!("[osc-control] {}", e);
+            return;
+        }
+    }
+}
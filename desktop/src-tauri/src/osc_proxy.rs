@@ -0,0 +1,242 @@
+//! Sits between AbletonOSC and the bridge, so the app sees AbletonOSC's raw
+//! traffic
+//!
+//! AbletonOSC always replies to port 11001 on the sender's host. While the
+//! proxy runs it takes that port and passes each datagram on to the bridge,
+//! which listens on [`BRIDGE_RECEIVE_PORT`] instead. Otherwise the bridge takes
+//! 11001 itself.
+//!
+//! With `--osc-capture` the proxy also takes what the bridge sends, on
+//! [`BRIDGE_SEND_PORT`], passes it on to AbletonOSC and records both ways (see
+//! `osc_capture`).
+
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use mission_control_protocol::control;
+use tauri::{AppHandle, Manager};
+
+use crate::bridge::OscRoute;
+use crate::osc::{self, OSC_RECEIVE_PORT};
+use crate::osc_capture::{Direction, Recorder};
+use crate::osc_relay;
+use crate::settings::Settings;
+use crate::AppState;
+
+/// Port the bridge listens on for AbletonOSC's replies while the proxy runs
+pub const BRIDGE_RECEIVE_PORT: u16 = 11011;
+/// Port the bridge sends to instead of AbletonOSC while capturing
+pub const BRIDGE_SEND_PORT: u16 = 11010;
+
+/// How often the proxy checks whether it's been stopped
+const POLL: Duration = Duration::from_millis(200);
+
+#[derive(Default)]
+pub struct OscProxy {
+    running: Mutex<Option<Running>>,
+    /// The capture, kept across restarts so a launch records to one file
+    recorder: Mutex<Option<Arc<Mutex<Recorder>>>>,
+}
+
+/// The proxy's threads, one for each way it passes datagrams on
+struct Running {
+    /// Set to stop the threads
+    stop: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Running {
+    /// Stop the threads and wait for them to let go of their ports, which
+    /// takes up to [`POLL`]
+    fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        for thread in self.threads {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Whether `settings` need the proxy. It also runs while capturing.
+pub fn needed(settings: &Settings) -> bool {
+    settings.relay.raw()
+}
+
+/// Folder to record captures in, from `--osc-capture`
+fn capture_dir(app: &AppHandle) -> Option<PathBuf> {
+    app.state::<AppState>().cli.osc_capture.clone()
+}
+
+/// Where the bridge should send to and listen on for AbletonOSC (see
+/// `--osc-host`, `--osc-send-port` and `--osc-receive-port` in
+/// server/standalone.ts)
+pub fn bridge_route(app: &AppHandle, settings: &Settings) -> OscRoute {
+    if capture_dir(app).is_some() {
+        OscRoute {
+            host: Ipv4Addr::LOCALHOST.to_string(),
+            send_port: BRIDGE_SEND_PORT,
+            receive_port: BRIDGE_RECEIVE_PORT,
+        }
+    } else if needed(settings) {
+        OscRoute {
+            receive_port: BRIDGE_RECEIVE_PORT,
+            ..OscRoute::direct(&settings.bridge.osc_host)
+        }
+    } else {
+        OscRoute::direct(&settings.bridge.osc_host)
+    }
+}
+
+/// Start the proxy if `settings` need it or capturing, stopping any already
+/// running. Returns once the old proxy has let go of its ports, so the bridge
+/// can then be (re)started with [`bridge_route`] to match.
+pub fn start(app: &AppHandle, settings: &Settings) {
+    let state = app.state::<OscProxy>();
+    let running = state.running.lock().unwrap().take();
+    if let Some(running) = running {
+        running.stop();
+    }
+    let capture_dir = capture_dir(app);
+    if !needed(settings) && capture_dir.is_none() {
+        return;
+    }
+
+    // Replies from another machine arrive on our LAN address, not loopback
+    let live = osc::resolve(&settings.bridge.osc_host);
+    let loopback = live.as_ref().is_ok_and(|address| address.ip().is_loopback());
+    let listen_ip = if loopback { Ipv4Addr::LOCALHOST } else { Ipv4Addr::UNSPECIFIED };
+    let bridge = SocketAddr::from((Ipv4Addr::LOCALHOST, BRIDGE_RECEIVE_PORT));
+
+    let recorder = capture_dir.as_ref().and_then(|dir| {
+        let mut recorder = state.recorder.lock().unwrap();
+        if recorder.is_none() {
+            match Recorder::create(dir, &settings.bridge.osc_host) {
+                Ok((created, path)) => {
+                    log::info!("[osc-capture] Recording to {}", path.display());
+                    *recorder = Some(Arc::new(Mutex::new(created)));
+                }
+                Err(e) => log::error!("[osc-capture] {}", e),
+            }
+        }
+        recorder.clone()
+    });
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let mut threads = Vec::new();
+
+    if capture_dir.is_some() {
+        match live {
+            Ok(live) => {
+                let recorder = recorder.clone();
+                threads.push(pass_on((Ipv4Addr::LOCALHOST, BRIDGE_SEND_PORT).into(), live, stop.clone(), move |datagram| {
+                    if let Some(recorder) = &recorder {
+                        recorder.lock().unwrap().record(Direction::ToLive, datagram);
+                    }
+                }));
+            }
+            Err(e) => log::error!("[osc-proxy] Could not resolve {}: {}", settings.bridge.osc_host, e),
+        }
+    }
+
+    let handle = app.clone();
+    threads.push(pass_on((listen_ip, OSC_RECEIVE_PORT).into(), bridge, stop.clone(), move |datagram| {
+        if let Some(recorder) = &recorder {
+            recorder.lock().unwrap().record(Direction::FromLive, datagram);
+        }
+        osc_relay::relay_raw(&handle, datagram);
+    }));
+
+    *state.running.lock().unwrap() = Some(Running { stop, threads });
+}
+
+/// Pass each datagram arriving on `listen` on to `to`, after `seen`, until
+/// `stop` is set
+fn pass_on(
+    listen: SocketAddr,
+    to: SocketAddr,
+    stop: Arc<AtomicBool>,
+    mut seen: impl FnMut(&[u8]) + Send + 'static,
+) -> JoinHandle<()> {
+    thread::spawn(move || {
+        // The bridge may take a moment to let go of the port
+        let socket = (0..10)
+            .take_while(|_| !stop.load(Ordering::Relaxed))
+            .find_map(|_| match UdpSocket::bind(listen) {
+                Ok(socket) => Some(socket),
+                Err(_) => {
+                    thread::sleep(POLL);
+                    None
+                }
+            });
+        let Some(socket) = socket else {
+            if !stop.load(Ordering::Relaxed) {
+                log::error!("[osc-proxy] Could not listen on port {}; is the bridge using it?", listen.port());
+            }
+            return;
+        };
+        if let Err(e) = socket.set_read_timeout(Some(POLL)) {
+            log::error!("[osc-proxy] Could not set read timeout: {}", e);
+            return;
+        }
+        log::info!("[osc-proxy] Passing port {} on to {}", listen.port(), to);
+
+        let mut buffer = vec![0; control::MAX_DATAGRAM];
+        while !stop.load(Ordering::Relaxed) {
+            let length = match socket.recv_from(&mut buffer) {
+                Ok((length, _)) => length,
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+                Err(e) => {
+                    log::warn!("[osc-proxy] Could not receive: {}", e);
+                    continue;
+                }
+            };
+            let datagram = &buffer[..length];
+            if let Err(e) = socket.send_to(datagram, to) {
+                log::debug!("[osc-proxy] Could not pass on to {}: {}", to, e);
+            }
+            seen(datagram);
+        }
+        log::info!("[osc-proxy] Stopped passing port {} on", listen.port());
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn free_port() -> SocketAddr {
+        UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap()
+    }
+
+    #[test]
+    fn passes_datagrams_on_and_lets_go_of_the_port_when_stopped() {
+        let listen = free_port();
+        let to = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        to.set_read_timeout(Some(Duration::from_millis(100))).unwrap();
+        let (seen, datagrams) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = pass_on(listen, to.local_addr().unwrap(), stop.clone(), move |datagram| {
+            let _ = seen.send(datagram.to_vec());
+        });
+
+        let sender = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let mut buffer = [0; 16];
+        // Until the thread has bound the port
+        let length = (0..20)
+            .find_map(|_| {
+                sender.send_to(b"beat", listen).unwrap();
+                to.recv(&mut buffer).ok()
+            })
+            .expect("the proxy should pass the datagram on");
+        assert_eq!(&buffer[..length], b"beat");
+        assert_eq!(datagrams.recv_timeout(Duration::from_secs(2)).unwrap(), b"beat");
+
+        Running { stop, threads: vec![thread] }.stop();
+        assert!(UdpSocket::bind(listen).is_ok());
+    }
+}
@@ -0,0 +1,523 @@
+//! OSC relay: mirrors Live's beat, transport and clips to other OSC apps, such
+//! as lighting and visuals rigs on the network
+//!
+//! Relays either changes to the bridge's session, as AbletonOSC-style messages,
+//! or everything AbletonOSC sends (through `osc_proxy`). Either way, addresses
+//! are filtered and can be remapped before sending.
+
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::Mutex;
+
+use mission_control_protocol::{control, Snapshot};
+use rosc::address::{verify_address, Matcher, OscAddress};
+use rosc::{OscMessage, OscPacket, OscType};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::settings::FieldError;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RelaySettings {
+    /// Where to send, as `host:port`. The relay is off without any.
+    pub targets: Vec<String>,
+    pub source: RelaySource,
+    /// OSC address patterns to relay, e.g. `/live/song/get/*`. Everything if
+    /// empty.
+    pub filter: Vec<String>,
+    /// Checked in order; the first that matches renames the address
+    pub rules: Vec<RemapRule>,
+}
+
+/// What the relay sends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelaySource {
+    /// Beat, transport and clip changes from the bridge's session
+    #[default]
+    State,
+    /// Every message AbletonOSC sends the bridge
+    Raw,
+}
+
+/// e.g. `{ "from": "/live/song/get/beat", "to": "/lx/beat" }`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RemapRule {
+    /// OSC address pattern, which may use `*`, `?`, `[a-z]` and `{a,b}`
+    pub from: String,
+    /// Address to send matching messages to instead
+    pub to: String,
+}
+
+impl RelaySettings {
+    /// Relaying anything at all
+    pub fn enabled(&self) -> bool {
+        !self.targets.is_empty()
+    }
+
+    /// Relaying AbletonOSC's own messages, which needs `osc_proxy`
+    pub fn raw(&self) -> bool {
+        self.enabled() && self.source == RelaySource::Raw
+    }
+
+    /// Check targets, patterns and addresses. Errors are for `relay.targets`,
+    /// `relay.filter` and `relay.rules`, naming the first bad line of each.
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+        let mut error = |field: &'static str, index: usize, message: String| {
+            errors.push(FieldError {
+                field: field.into(),
+                message: format!("Line {}: {}", index + 1, message),
+            });
+        };
+
+        if let Some((index, message)) = self.targets.iter().enumerate().find_map(|(index, target)| {
+            let message = match target.rsplit_once(':') {
+                None => "Enter a host and port, e.g. 192.168.1.20:9000.".to_string(),
+                Some(("", _)) => "Enter a host before the port.".to_string(),
+                Some((_, port)) if !port.parse::<u16>().is_ok_and(|port| port > 0) => {
+                    format!("{} isn't a port number.", port)
+                }
+                Some(_) => return None,
+            };
+            Some((index, message))
+        }) {
+            error("relay.targets", index, message);
+        }
+
+        if let Some(index) = self.filter.iter().position(|pattern| Matcher::new(pattern).is_err()) {
+            error("relay.filter", index, "Enter an OSC address pattern, e.g. /live/song/get/*.".to_string());
+        }
+
+        if let Some((index, message)) = self.rules.iter().enumerate().find_map(|(index, rule)| {
+            let message = if Matcher::new(&rule.from).is_err() {
+                format!("{:?} isn't an OSC address pattern.", rule.from)
+            } else if verify_address(&rule.to).is_err() {
+                "Enter the address to send to after ->, e.g. /lx/beat.".to_string()
+            } else {
+                return None;
+            };
+            Some((index, message))
+        }) {
+            error("relay.rules", index, message);
+        }
+        errors
+    }
+}
+
+#[derive(Default)]
+pub struct OscRelay {
+    relay: Mutex<Option<Relay>>,
+    /// The session as last relayed, so only changes are sent
+    last: Mutex<Option<Observed>>,
+}
+
+struct Relay {
+    /// For IPv4 targets, and IPv6 ones, if there are any
+    ipv4: Option<UdpSocket>,
+    ipv6: Option<UdpSocket>,
+    targets: Vec<SocketAddr>,
+    source: RelaySource,
+    filter: Vec<Matcher>,
+    rules: Vec<(Matcher, String)>,
+}
+
+/// What the relay mirrors from the session
+#[derive(Debug, Clone, PartialEq)]
+pub struct Observed {
+    /// Whole beats, while playing
+    beat: Option<i64>,
+    tempo: f64,
+    playing: bool,
+    recording: bool,
+    /// Status of each clip slot by track, then scene, as AbletonOSC reports it:
+    /// 0 stopped or empty, 1 playing, 2 triggered, 3 recording
+    clips: Vec<Vec<i32>>,
+}
+
+impl Observed {
+    /// `None` without a session from Live
+    pub fn of(snapshot: &Snapshot) -> Option<Observed> {
+        let session = snapshot.live_session()?;
+
+        Some(Observed {
+            beat: session.is_playing.then(|| session.beat_time.floor() as i64),
+            tempo: session.tempo,
+            playing: session.is_playing,
+            recording: session.is_recording,
+            clips: session
+                .tracks
+                .iter()
+                .map(|track| {
+                    track
+                        .clips
+                        .iter()
+                        .map(|slot| match &slot.clip {
+                            Some(clip) if clip.is_recording => 3,
+                            Some(clip) if clip.is_triggered => 2,
+                            Some(clip) if clip.is_playing => 1,
+                            _ => 0,
+                        })
+                        .collect()
+                })
+                .collect(),
+        })
+    }
+
+    /// AbletonOSC-style messages for what changed since `last`, or for
+    /// everything if there's no `last`
+    fn messages(&self, last: Option<&Observed>) -> Vec<OscMessage> {
+        let message = |addr: &str, args: Vec<OscType>| OscMessage { addr: addr.to_string(), args };
+        let flag = |value: bool| OscType::Int(value.into());
+        let mut messages = Vec::new();
+
+        if let Some(beat) = self.beat.filter(|beat| last.is_none_or(|last| last.beat != Some(*beat))) {
+            messages.push(message("/live/song/get/beat", vec![OscType::Int(beat as i32)]));
+        }
+        if last.is_none_or(|last| last.tempo != self.tempo) {
+            messages.push(message("/live/song/get/tempo", vec![OscType::Float(self.tempo as f32)]));
+        }
+        if last.is_none_or(|last| last.playing != self.playing) {
+            messages.push(message("/live/song/get/is_playing", vec![flag(self.playing)]));
+        }
+        if last.is_none_or(|last| last.recording != self.recording) {
+            messages.push(message("/live/song/get/record_mode", vec![flag(self.recording)]));
+        }
+        for (track, clips) in self.clips.iter().enumerate() {
+            for (scene, status) in clips.iter().enumerate() {
+                let before = last.and_then(|last| last.clips.get(track)?.get(scene));
+                // New slots only matter once something's in them
+                if before.map_or(last.is_none() || *status != 0, |before| before != status) {
+                    messages.push(message(
+                        "/live/clip/get/playing_status",
+                        vec![OscType::Int(track as i32), OscType::Int(scene as i32), OscType::Int(*status)],
+                    ));
+                }
+            }
+        }
+        messages
+    }
+}
+
+/// Start relaying to the targets in `settings`, replacing any earlier relay.
+/// Does nothing without targets.
+pub fn start(app: &AppHandle, settings: &RelaySettings) {
+    let state = app.state::<OscRelay>();
+    state.last.lock().unwrap().take();
+
+    let relay = if settings.enabled() {
+        match Relay::new(settings) {
+            Ok(relay) => {
+                log::info!("[relay] Relaying {:?} to {} target(s)", settings.source, relay.targets.len());
+                Some(relay)
+            }
+            Err(e) => {
+                log::warn!("[relay] {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    *state.relay.lock().unwrap() = relay;
+}
+
+impl Relay {
+    fn new(settings: &RelaySettings) -> Result<Relay, String> {
+        let mut targets = Vec::new();
+        for target in &settings.targets {
+            // Preferring IPv4, for hosts that have both
+            match target.to_socket_addrs().map(|addresses| addresses.min_by_key(SocketAddr::is_ipv6)) {
+                Ok(Some(address)) => targets.push(address),
+                Ok(None) => log::warn!("[relay] {} has no address", target),
+                Err(e) => log::warn!("[relay] Could not resolve {}: {}", target, e),
+            }
+        }
+        if targets.is_empty() {
+            return Err("No targets to relay to".to_string());
+        }
+
+        let bind = |address: SocketAddr| UdpSocket::bind(address).map_err(|e| format!("Could not open relay socket: {}", e));
+        let ipv4 = targets.iter().any(SocketAddr::is_ipv4).then(|| bind((Ipv4Addr::UNSPECIFIED, 0).into())).transpose()?;
+        let ipv6 = targets.iter().any(SocketAddr::is_ipv6).then(|| bind((Ipv6Addr::UNSPECIFIED, 0).into())).transpose()?;
+        // Validated with the settings, so any that don't parse are skipped
+        let filter = settings.filter.iter().filter_map(|pattern| Matcher::new(pattern).ok()).collect();
+        let rules = settings
+            .rules
+            .iter()
+            .filter_map(|rule| Some((Matcher::new(&rule.from).ok()?, rule.to.clone())))
+            .collect();
+
+        Ok(Relay {
+            ipv4,
+            ipv6,
+            targets,
+            source: settings.source,
+            filter,
+            rules,
+        })
+    }
+
+    /// Filter, remap and send a message to every target
+    fn send(&self, mut message: OscMessage) {
+        let Ok(address) = OscAddress::new(message.addr.clone()) else {
+            return;
+        };
+        if !self.filter.is_empty() && !self.filter.iter().any(|matcher| matcher.match_address(&address)) {
+            return;
+        }
+        if let Some((_, to)) = self.rules.iter().find(|(matcher, _)| matcher.match_address(&address)) {
+            message.addr = to.clone();
+        }
+
+        let bytes = match rosc::encoder::encode(&OscPacket::Message(message)) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                log::warn!("[relay] Could not encode message: {}", e);
+                return;
+            }
+        };
+        for target in &self.targets {
+            let socket = if target.is_ipv4() { &self.ipv4 } else { &self.ipv6 };
+            if let Some(Err(e)) = socket.as_ref().map(|socket| socket.send_to(&bytes, target)) {
+                log::debug!("[relay] Could not send to {}: {}", target, e);
+            }
+        }
+    }
+}
+
+/// What to relay from `snapshot`, to pass to [`relay_changes`] once done
+/// reading it. `None` unless relaying the session.
+pub fn observe(app: &AppHandle, snapshot: &Snapshot) -> Option<Option<Observed>> {
+    let state = app.try_state::<OscRelay>()?;
+    let relaying = state.relay.lock().unwrap().as_ref().is_some_and(|relay| relay.source == RelaySource::State);
+    relaying.then(|| Observed::of(snapshot))
+}
+
+/// Relay what changed since the session was last relayed
+pub fn relay_changes(app: &AppHandle, observed: Option<Observed>) {
+    let state = app.state::<OscRelay>();
+    let mut last = state.last.lock().unwrap();
+    if let (Some(observed), Some(relay)) = (&observed, state.relay.lock().unwrap().as_ref()) {
+        for message in observed.messages(last.as_ref()) {
+            relay.send(message);
+        }
+    }
+    // Without a session, everything is sent again once there is one
+    *last = observed;
+}
+
+/// Relay a datagram from AbletonOSC, if relaying those
+pub fn relay_raw(app: &AppHandle, datagram: &[u8]) {
+    let state = app.state::<OscRelay>();
+    let relay = state.relay.lock().unwrap();
+    let Some(relay) = relay.as_ref().filter(|relay| relay.source == RelaySource::Raw) else {
+        return;
+    };
+    match rosc::decoder::decode_udp(datagram) {
+        Ok((_, packet)) => {
+            for message in control::flatten(packet) {
+                relay.send(message);
+            }
+        }
+        Err(e) => log::debug!("[relay] Not an OSC packet: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn errors(settings: &RelaySettings) -> Vec<(String, String)> {
+        settings.validate().into_iter().map(|error| (error.field.into_owned(), error.message)).collect()
+    }
+
+    fn error(field: &str, message: &str) -> (String, String) {
+        (field.to_string(), message.to_string())
+    }
+
+    fn rule(from: &str, to: &str) -> RemapRule {
+        RemapRule { from: from.to_string(), to: to.to_string() }
+    }
+
+    #[test]
+    fn accepts_targets_patterns_and_rules() {
+        let settings = RelaySettings {
+            targets: vec!["192.168.1.20:9000".into(), "lights.local:7000".into(), "[::1]:9000".into()],
+            source: RelaySource::Raw,
+            filter: vec!["/live/song/get/*".into(), "/live/clip/get/{playing_status,name}".into()],
+            rules: vec![rule("/live/song/get/beat", "/lx/beat")],
+        };
+        assert_eq!(errors(&settings), []);
+    }
+
+    #[test]
+    fn names_the_first_bad_target() {
+        for (target, message) in [
+            ("192.168.1.20", "Line 2: Enter a host and port, e.g. 192.168.1.20:9000."),
+            (":9000", "Line 2: Enter a host before the port."),
+            ("lights.local:0", "Line 2: 0 isn't a port number."),
+            ("lights.local:port", "Line 2: port isn't a port number."),
+            ("lights.local:70000", "Line 2: 70000 isn't a port number."),
+        ] {
+            let settings = RelaySettings {
+                targets: vec!["127.0.0.1:9000".into(), target.into(), "also bad".into()],
+                ..RelaySettings::default()
+            };
+            assert_eq!(errors(&settings), [error("relay.targets", message)]);
+        }
+    }
+
+    #[test]
+    fn checks_patterns_and_rules() {
+        let settings = RelaySettings {
+            filter: vec!["/live/*".into(), "live/song".into()],
+            rules: vec![rule("/live/[song", "/lx/song"), rule("/live/*", "/lx/*")],
+            ..RelaySettings::default()
+        };
+        assert_eq!(
+            errors(&settings),
+            [
+                error("relay.filter", "Line 2: Enter an OSC address pattern, e.g. /live/song/get/*."),
+                error("relay.rules", "Line 1: \"/live/[song\" isn't an OSC address pattern."),
+            ]
+        );
+
+        let settings = RelaySettings { rules: vec![rule("/live/*", "/lx/*")], ..RelaySettings::default() };
+        assert_eq!(errors(&settings), [error("relay.rules", "Line 1: Enter the address to send to after ->, e.g. /lx/beat.")]);
+    }
+
+    fn message(addr: &str, args: Vec<OscType>) -> OscMessage {
+        OscMessage { addr: addr.to_string(), args }
+    }
+
+    fn target(address: SocketAddr) -> UdpSocket {
+        let target = UdpSocket::bind(address).unwrap();
+        target.set_read_timeout(Some(Duration::from_millis(200))).unwrap();
+        target
+    }
+
+    /// A relay to a local socket, and that socket
+    fn relay(filter: &[&str], rules: Vec<RemapRule>) -> (Relay, UdpSocket) {
+        let target = target((Ipv4Addr::LOCALHOST, 0).into());
+        let settings = RelaySettings {
+            targets: vec![target.local_addr().unwrap().to_string()],
+            source: RelaySource::Raw,
+            filter: filter.iter().map(|pattern| pattern.to_string()).collect(),
+            rules,
+        };
+        (Relay::new(&settings).unwrap(), target)
+    }
+
+    fn receive(target: &UdpSocket) -> Option<OscMessage> {
+        let mut buffer = [0; 1024];
+        let length = target.recv(&mut buffer).ok()?;
+        match rosc::decoder::decode_udp(&buffer[..length]).unwrap().1 {
+            OscPacket::Message(message) => Some(message),
+            OscPacket::Bundle(_) => None,
+        }
+    }
+
+    #[test]
+    fn remaps_with_the_first_matching_rule() {
+        let (relay, target) = relay(
+            &[],
+            vec![rule("/live/song/get/beat", "/lx/beat"), rule("/live/song/get/*", "/lx/song"), rule("/live/song/get/beat", "/never")],
+        );
+        relay.send(message("/live/song/get/beat", vec![OscType::Int(17)]));
+        relay.send(message("/live/song/get/tempo", vec![OscType::Float(120.0)]));
+        relay.send(message("/live/clip/get/name", vec![]));
+
+        assert_eq!(receive(&target), Some(message("/lx/beat", vec![OscType::Int(17)])));
+        assert_eq!(receive(&target), Some(message("/lx/song", vec![OscType::Float(120.0)])));
+        assert_eq!(receive(&target), Some(message("/live/clip/get/name", vec![])));
+    }
+
+    #[test]
+    fn sends_to_ipv4_and_ipv6_targets() {
+        let targets = [target((Ipv4Addr::LOCALHOST, 0).into()), target((Ipv6Addr::LOCALHOST, 0).into())];
+        let settings = RelaySettings {
+            targets: targets.iter().map(|target| target.local_addr().unwrap().to_string()).collect(),
+            source: RelaySource::Raw,
+            ..RelaySettings::default()
+        };
+        assert_eq!(errors(&settings), []);
+
+        Relay::new(&settings).unwrap().send(message("/live/song/get/beat", vec![OscType::Int(17)]));
+        for target in &targets {
+            assert_eq!(receive(target), Some(message("/live/song/get/beat", vec![OscType::Int(17)])));
+        }
+    }
+
+    #[test]
+    fn filters_before_remapping() {
+        let (relay, target) = relay(&["/live/song/get/{beat,tempo}"], vec![rule("/live/song/get/beat", "/lx/beat")]);
+        relay.send(message("/live/song/get/is_playing", vec![OscType::Int(1)]));
+        relay.send(message("/live/song/get/beat", vec![OscType::Int(17)]));
+
+        assert_eq!(receive(&target), Some(message("/lx/beat", vec![OscType::Int(17)])));
+        assert_eq!(receive(&target), None);
+    }
+
+    fn observed(clips: Vec<Vec<i32>>) -> Observed {
+        Observed {
+            beat: Some(16),
+            tempo: 120.0,
+            playing: true,
+            recording: false,
+            clips,
+        }
+    }
+
+    fn addresses(messages: &[OscMessage]) -> Vec<&str> {
+        messages.iter().map(|message| message.addr.as_str()).collect()
+    }
+
+    #[test]
+    fn sends_everything_the_first_time() {
+        let messages = observed(vec![vec![1, 0]]).messages(None);
+        assert_eq!(
+            messages,
+            [
+                message("/live/song/get/beat", vec![OscType::Int(16)]),
+                message("/live/song/get/tempo", vec![OscType::Float(120.0)]),
+                message("/live/song/get/is_playing", vec![OscType::Int(1)]),
+                message("/live/song/get/record_mode", vec![OscType::Int(0)]),
+                message("/live/clip/get/playing_status", vec![OscType::Int(0), OscType::Int(0), OscType::Int(1)]),
+                message("/live/clip/get/playing_status", vec![OscType::Int(0), OscType::Int(1), OscType::Int(0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn sends_only_what_changed() {
+        let last = observed(vec![vec![1, 0]]);
+        assert_eq!(last.messages(Some(&last)), []);
+
+        let next = Observed { beat: Some(17), ..observed(vec![vec![0, 2]]) };
+        assert_eq!(
+            next.messages(Some(&last)),
+            [
+                message("/live/song/get/beat", vec![OscType::Int(17)]),
+                message("/live/clip/get/playing_status", vec![OscType::Int(0), OscType::Int(0), OscType::Int(0)]),
+                message("/live/clip/get/playing_status", vec![OscType::Int(0), OscType::Int(1), OscType::Int(2)]),
+            ]
+        );
+
+        // Stopping sends no beat, only that it stopped
+        let stopped = Observed { beat: None, playing: false, ..last.clone() };
+        assert_eq!(addresses(&stopped.messages(Some(&last))), ["/live/song/get/is_playing"]);
+    }
+
+    #[test]
+    fn sends_new_slots_once_they_have_something() {
+        let last = observed(vec![vec![0]]);
+        let grown = observed(vec![vec![0, 0], vec![0, 3]]);
+        assert_eq!(
+            grown.messages(Some(&last)),
+            [message("/live/clip/get/playing_status", vec![OscType::Int(1), OscType::Int(1), OscType::Int(3)])]
+        );
+    }
+}
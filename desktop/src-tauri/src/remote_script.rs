@@ -0,0 +1,111 @@
+//! Installing the bundled AbletonOSC remote script into Live's User Library
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::settings::Settings;
+
+/// Folder name of the script, in the app's resources and in Remote Scripts
+/// (matches `remoteScriptName` in server/config.ts)
+pub const SCRIPT_NAME: &str = "AbletonOSC";
+
+/// Remote Scripts folder in Live's default User Library
+pub fn default_remote_scripts_path() -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or("Could not find home directory")?;
+
+    #[cfg(target_os = "macos")]
+    return Ok(home.join("Music/Ableton/User Library/Remote Scripts"));
+
+    #[cfg(target_os = "windows")]
+    return Ok(home.join("Documents/Ableton/User Library/Remote Scripts"));
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    return Ok(wine_documents(&home).join("Ableton/User Library/Remote Scripts"));
+}
+
+/// Live has no Linux build, so on Linux it runs under Wine and keeps its User
+/// Library in the Wine prefix's Documents folder
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn wine_documents(home: &Path) -> PathBuf {
+    let prefix = std::env::var_os("WINEPREFIX")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".wine"));
+    let user = std::env::var("USER").unwrap_or_else(|_| {
+        home.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default()
+    });
+
+    prefix.join("drive_c/users").join(user).join("Documents")
+}
+
+/// Where to install AbletonOSC: the folder from settings, or the platform default
+pub fn remote_scripts_path(settings: &Settings) -> Result<PathBuf, String> {
+    match &settings.ableton.remote_scripts_path {
+        Some(path) => Ok(path.clone()),
+        None => default_remote_scripts_path(),
+    }
+}
+
+/// Copy the bundled script from `resource_dir` into `remote_scripts`, replacing
+/// any existing copy. Returns the installed path.
+pub fn install(resource_dir: &Path, remote_scripts: &Path) -> Result<PathBuf, String> {
+    let source = resource_dir.join(SCRIPT_NAME);
+    let dest_path = remote_scripts.join(SCRIPT_NAME);
+
+    // Create Remote Scripts directory if it doesn't exist
+    fs::create_dir_all(remote_scripts)
+        .map_err(|e| format!("Could not create Remote Scripts directory: {}", e))?;
+
+    // Remove existing installation if present
+    if dest_path.exists() {
+        fs::remove_dir_all(&dest_path)
+            .map_err(|e| format!("Could not remove existing installation: {}", e))?;
+    }
+
+    // Copy AbletonOSC to Remote Scripts
+    copy_dir_recursive(&source, &dest_path)?;
+
+    log::info!("Remote script installed to: {:?}", dest_path);
+
+    Ok(dest_path)
+}
+
+/// Remove the script from `remote_scripts`. Returns the removed path, or `None`
+/// if it wasn't installed.
+pub fn uninstall(remote_scripts: &Path) -> Result<Option<PathBuf>, String> {
+    let path = remote_scripts.join(SCRIPT_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    fs::remove_dir_all(&path).map_err(|e| format!("Could not remove {:?}: {}", path, e))?;
+    log::info!("Remote script removed from: {:?}", path);
+
+    Ok(Some(path))
+}
+
+/// Whether the script is installed in `remote_scripts`
+pub fn is_installed(remote_scripts: &Path) -> bool {
+    remote_scripts.join(SCRIPT_NAME).is_dir()
+}
+
+fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<(), String> {
+    fs::create_dir_all(dst)
+        .map_err(|e| format!("Could not create directory {:?}: {}", dst, e))?;
+
+    for entry in fs::read_dir(src).map_err(|e| format!("Could not read dir: {}", e))? {
+        let entry = entry.map_err(|e| format!("Could not read entry: {}", e))?;
+        let path = entry.path();
+        let dest_path = dst.join(entry.file_name());
+
+        if path.is_dir() {
+            copy_dir_recursive(&path, &dest_path)?;
+        } else {
+            fs::copy(&path, &dest_path)
+                .map_err(|e| format!("Could not copy {:?}: {}", path, e))?;
+        }
+    }
+
+    Ok(())
+}
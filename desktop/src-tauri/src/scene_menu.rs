@@ -0,0 +1,210 @@
+//! Scenes submenu in the tray, for launching scenes without the UI
+
+use std::sync::Mutex;
+
+use mission_control_protocol::{ClientMessage, SessionState, Snapshot};
+use tauri::menu::{CheckMenuItem, MenuItem, Submenu};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::bridge_client;
+
+const ID_PREFIX: &str = "scene_fire_";
+
+pub struct SceneMenu {
+    submenu: Submenu<Wry>,
+    items: Mutex<Vec<CheckMenuItem<Wry>>>,
+    /// Shown instead of scenes when there are none
+    empty: MenuItem<Wry>,
+    shown: Mutex<Option<View>>,
+}
+
+/// The scenes as listed, so items are only rebuilt when scenes are added,
+/// removed or renamed
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    /// Connected with a session from Live
+    available: bool,
+    scenes: Vec<Scene>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Scene {
+    id: i32,
+    label: String,
+    state: SceneState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SceneState {
+    Stopped,
+    /// Every clip in the scene is playing
+    Playing,
+    /// A clip in the scene will start at the next quantization point
+    Triggered,
+}
+
+impl View {
+    pub fn of(snapshot: &Snapshot) -> View {
+        match snapshot.live_session() {
+            Some(session) => View {
+                available: true,
+                scenes: session
+                    .scenes
+                    .iter()
+                    .enumerate()
+                    .map(|(index, scene)| Scene {
+                        id: scene.id,
+                        // Live numbers unnamed scenes
+                        label: if scene.name.trim().is_empty() {
+                            format!("Scene {}", index + 1)
+                        } else {
+                            scene.name.clone()
+                        },
+                        state: scene_state(session, index),
+                    })
+                    .collect(),
+            },
+            None => View {
+                available: false,
+                scenes: Vec::new(),
+            },
+        }
+    }
+
+    /// Whether `other` lists the same scenes, whatever they're doing
+    fn same_scenes(&self, other: &View) -> bool {
+        self.scenes.len() == other.scenes.len()
+            && self
+                .scenes
+                .iter()
+                .zip(&other.scenes)
+                .all(|(a, b)| a.id == b.id && a.label == b.label)
+    }
+}
+
+/// What the clips in row `index` are doing. Live doesn't report scenes playing,
+/// so a scene counts as playing when all of its clips are.
+pub fn scene_state(session: &SessionState, index: usize) -> SceneState {
+    let clips: Vec<_> = session
+        .tracks
+        .iter()
+        .filter_map(|track| track.clips.get(index)?.clip.as_ref())
+        .collect();
+
+    if clips.iter().any(|clip| clip.is_triggered) {
+        SceneState::Triggered
+    } else if !clips.is_empty() && clips.iter().all(|clip| clip.is_playing) {
+        SceneState::Playing
+    } else {
+        SceneState::Stopped
+    }
+}
+
+fn item_text(scene: &Scene) -> String {
+    match scene.state {
+        SceneState::Triggered => format!("{} (triggered)", scene.label),
+        _ => scene.label.clone(),
+    }
+}
+
+impl SceneMenu {
+    pub fn new(app: &AppHandle) -> tauri::Result<SceneMenu> {
+        let empty = MenuItem::with_id(app, "scene_none", "No Scenes", false, None::<&str>)?;
+        let submenu = Submenu::with_items(app, "Scenes", false, &[&empty])?;
+        Ok(SceneMenu {
+            submenu,
+            items: Mutex::new(Vec::new()),
+            empty,
+            shown: Mutex::new(None),
+        })
+    }
+
+    pub fn submenu(&self) -> &Submenu<Wry> {
+        &self.submenu
+    }
+
+    /// Replace the items with one per scene in `view`
+    fn rebuild(&self, app: &AppHandle, view: &View) -> tauri::Result<()> {
+        let mut items = self.items.lock().unwrap();
+        if items.is_empty() {
+            self.submenu.remove(&self.empty)?;
+        }
+        for item in items.drain(..) {
+            self.submenu.remove(&item)?;
+        }
+
+        for scene in &view.scenes {
+            let checked = scene.state != SceneState::Stopped;
+            let item = CheckMenuItem::with_id(app, format!("{}{}", ID_PREFIX, scene.id), item_text(scene), true, checked, None::<&str>)?;
+            self.submenu.append(&item)?;
+            items.push(item);
+        }
+        if items.is_empty() {
+            self.submenu.append(&self.empty)?;
+        }
+        Ok(())
+    }
+
+    /// Update the marks on existing items, for scenes whose state changed
+    fn mark(&self, shown: &View, view: &View) -> tauri::Result<()> {
+        let items = self.items.lock().unwrap();
+        for ((item, old), scene) in items.iter().zip(&shown.scenes).zip(&view.scenes) {
+            if old.state != scene.state {
+                item.set_text(item_text(scene))?;
+                item.set_checked(scene.state != SceneState::Stopped)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Show `view` in the tray, if it differs from what's shown
+pub fn update(app: &AppHandle, view: View) {
+    let Some(menu) = app.try_state::<SceneMenu>() else {
+        return;
+    };
+    let shown = {
+        // Not held while updating, as menu changes wait for the main thread
+        let mut shown = menu.shown.lock().unwrap();
+        if shown.as_ref() == Some(&view) {
+            return;
+        }
+        shown.replace(view.clone())
+    };
+
+    let result = match &shown {
+        Some(shown) if shown.same_scenes(&view) => menu.mark(shown, &view),
+        _ => menu.rebuild(app, &view),
+    }
+    .and_then(|_| menu.submenu.set_enabled(view.available));
+    if let Err(e) = result {
+        log::error!("Failed to update tray menu: {}", e);
+    }
+}
+
+/// Handle a click on a scene. Returns `false` if `id` isn't one.
+pub fn handle_menu_event(app: &AppHandle, id: &str) -> bool {
+    let Some(scene_id) = id.strip_prefix(ID_PREFIX).and_then(|id| id.parse::<i32>().ok()) else {
+        return false;
+    };
+    if let Err(e) = bridge_client::send(app, ClientMessage::SceneFire { scene_id }) {
+        log::error!("[tray] {}", e);
+    }
+
+    // Clicking toggled the check mark; put it back until Live reports the clips
+    let menu = app.state::<SceneMenu>();
+    let shown = menu.shown.lock().unwrap().clone();
+    // Being rebuilt, which sets every mark. Waiting would deadlock, as the
+    // rebuild waits for this (main) thread.
+    let Ok(items) = menu.items.try_lock() else {
+        return true;
+    };
+    if let Some(shown) = shown {
+        for (item, scene) in items.iter().zip(&shown.scenes) {
+            if scene.id == scene_id {
+                let _ = item.set_checked(scene.state != SceneState::Stopped);
+            }
+        }
+    }
+    true
+}
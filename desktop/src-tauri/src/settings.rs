@@ -0,0 +1,507 @@
+//! User settings persisted in the app config directory
+//!
+//! The file is versioned. Older files are migrated on load and written back in
+//! the current format the next time settings are saved. A file from a newer
+//! version keeps its version and the settings this one doesn't know about.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::midi_mapping::MidiSettings;
+use crate::osc;
+use crate::osc_control::OscControlSettings;
+use crate::osc_proxy;
+use crate::osc_relay::RelaySettings;
+use crate::updates::UpdateChannel;
+
+/// File name inside the app config directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Current settings file version. Bump it and add a step to `migrate` when the
+/// layout changes.
+pub const SETTINGS_VERSION: u32 = 2;
+
+/// Port the standalone bridge serves the UI on
+pub const DEFAULT_UI_PORT: u16 = 5555;
+/// Sidecar binary started as the bridge (see `externalBin` in tauri.conf.json)
+pub const DEFAULT_SIDECAR: &str = "mission-control-bridge";
+/// Ableton Live runs on this machine by default
+pub const DEFAULT_OSC_HOST: &str = "localhost";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Settings {
+    pub version: u32,
+    pub bridge: BridgeSettings,
+    pub ableton: AbletonSettings,
+    pub ui: UiSettings,
+    pub updates: UpdateSettings,
+    /// Global shortcuts, which work whichever app is focused
+    pub shortcuts: Vec<Shortcut>,
+    pub midi: MidiSettings,
+    /// Mirrors Live's state to other OSC apps
+    pub relay: RelaySettings,
+    /// Lets OSC controllers fire scenes and clips
+    pub osc_control: OscControlSettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct BridgeSettings {
+    /// Port for the web UI and WebSocket (release builds; dev uses Vite's)
+    pub ui_port: u16,
+    pub sidecar: String,
+    /// Host running Ableton Live with AbletonOSC
+    pub osc_host: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AbletonSettings {
+    /// Where to install AbletonOSC, if not the platform's default User Library
+    pub remote_scripts_path: Option<PathBuf>,
+    /// Join the Ableton Link session to show its tempo, phase and peers
+    pub link: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UiSettings {
+    /// Open Mission Control when the app starts
+    pub open_on_launch: bool,
+    pub open_in: OpenIn,
+    /// Keep the Mission Control window above other windows
+    pub always_on_top: bool,
+    /// Show Mission Control full screen on `kiosk_monitor`, and keep it there
+    pub kiosk: bool,
+    /// Monitor name (or number, from 1) for kiosk mode; the primary if `None`
+    pub kiosk_monitor: Option<String>,
+}
+
+/// Where Open Mission Control shows the UI
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenIn {
+    /// A tab in the default browser
+    #[default]
+    Browser,
+    /// The app's own window
+    Window,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UpdateSettings {
+    /// Check for updates in the background
+    pub automatic: bool,
+    pub channel: UpdateChannel,
+    /// Versions the user chose to skip; automatic checks stay quiet about them
+    pub skipped_versions: Vec<String>,
+    /// Unix time (seconds) until which automatic update prompts are snoozed
+    pub snoozed_until: Option<u64>,
+}
+
+/// A global shortcut, e.g. `{ "keys": "Ctrl+Alt+Space", "action": "playStop" }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Shortcut {
+    /// Accelerator such as `CmdOrCtrl+Shift+P`
+    pub keys: String,
+    #[serde(flatten)]
+    pub action: ShortcutAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum ShortcutAction {
+    /// Play, or stop if playing
+    PlayStop,
+    TapTempo,
+    /// Stop the clips on every track
+    StopAll,
+    NextScene,
+    PreviousScene,
+    /// Fire scene number `scene`, from 1
+    FireScene { scene: u32 },
+}
+
+/// A setting that failed validation. `field` matches the input's name in the
+/// Settings window, e.g. `bridge.uiPort` or `shortcuts.0.keys`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldError {
+    pub field: Cow<'static, str>,
+    pub message: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            bridge: BridgeSettings::default(),
+            ableton: AbletonSettings::default(),
+            ui: UiSettings::default(),
+            updates: UpdateSettings::default(),
+            shortcuts: Vec::new(),
+            midi: MidiSettings::default(),
+            relay: RelaySettings::default(),
+            osc_control: OscControlSettings::default(),
+        }
+    }
+}
+
+impl Default for BridgeSettings {
+    fn default() -> Self {
+        BridgeSettings {
+            ui_port: DEFAULT_UI_PORT,
+            sidecar: DEFAULT_SIDECAR.to_string(),
+            osc_host: DEFAULT_OSC_HOST.to_string(),
+        }
+    }
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        UpdateSettings {
+            automatic: true,
+            channel: UpdateChannel::default(),
+            skipped_versions: Vec::new(),
+            snoozed_until: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Settings file for the app with this bundle identifier. Matches Tauri's
+    /// `app_config_dir`, and works before (or without) the app starting.
+    pub fn path(identifier: &str) -> Result<PathBuf, String> {
+        dirs::config_dir()
+            .map(|dir| dir.join(identifier).join(SETTINGS_FILE))
+            .ok_or_else(|| "Could not get config dir".to_string())
+    }
+
+    /// Load settings, falling back to defaults if the file is missing or unreadable
+    pub fn load(path: &Path) -> Settings {
+        let Ok(contents) = fs::read_to_string(path) else {
+            return Settings::default();
+        };
+
+        serde_json::from_str(&contents)
+            .map_err(|e| e.to_string())
+            .and_then(Settings::from_json)
+            .unwrap_or_else(|e| {
+                log::warn!("[settings] Could not parse {:?}, using defaults: {}", path, e);
+                Settings::default()
+            })
+    }
+
+    /// Parse settings written by any version of the app
+    pub fn from_json(mut value: Value) -> Result<Settings, String> {
+        let object = value.as_object_mut().ok_or("Settings must be a JSON object")?;
+        let version = object.get("version").and_then(Value::as_u64).unwrap_or(1) as u32;
+
+        if version > SETTINGS_VERSION {
+            log::warn!(
+                "[settings] Settings file is from a newer version ({}), keeping settings this version doesn't know",
+                version
+            );
+        }
+        for from in version..SETTINGS_VERSION {
+            log::info!("[settings] Migrating settings from version {}", from);
+            migrate(object, from);
+        }
+
+        let mut settings: Settings = serde_json::from_value(value)
+            .map_err(|e| format!("Could not read settings: {}", e))?;
+        settings.version = SETTINGS_VERSION;
+        Ok(settings)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Could not create settings directory: {}", e))?;
+        }
+
+        let mut value = serde_json::to_value(self)
+            .map_err(|e| format!("Could not serialize settings: {}", e))?;
+        if let Some(mut newer) = newer_file(path) {
+            if let Some(object) = value.as_object_mut() {
+                object.remove("version");
+            }
+            merge(&mut newer, value);
+            value = newer;
+        }
+
+        let contents = serde_json::to_string_pretty(&value)
+            .map_err(|e| format!("Could not serialize settings: {}", e))?;
+        fs::write(path, contents).map_err(|e| format!("Could not write settings: {}", e))
+    }
+
+    /// Check values that serde can't, such as port ranges and paths
+    pub fn validate(&self) -> Vec<FieldError> {
+        let mut errors = Vec::new();
+
+        let port = self.bridge.ui_port;
+        if port < 1024 {
+            errors.push(FieldError {
+                field: "bridge.uiPort".into(),
+                message: "Use a port from 1024 to 65535. Lower ports need administrator rights.".to_string(),
+            });
+        } else if port == osc::OSC_SEND_PORT || port == osc::OSC_RECEIVE_PORT {
+            errors.push(FieldError {
+                field: "bridge.uiPort".into(),
+                message: format!("Port {} is used by AbletonOSC.", port),
+            });
+        }
+
+        let sidecar = &self.bridge.sidecar;
+        if sidecar.is_empty() {
+            errors.push(FieldError {
+                field: "bridge.sidecar".into(),
+                message: "Enter the name of the bridge executable.".to_string(),
+            });
+        } else if !sidecar.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) {
+            errors.push(FieldError {
+                field: "bridge.sidecar".into(),
+                message: "Use the bundled executable's name, not a path.".to_string(),
+            });
+        }
+
+        let host = &self.bridge.osc_host;
+        if host.is_empty() {
+            errors.push(FieldError {
+                field: "bridge.oscHost".into(),
+                message: "Enter the host running Ableton Live, or localhost.".to_string(),
+            });
+        } else if !host.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | ':')) {
+            errors.push(FieldError {
+                field: "bridge.oscHost".into(),
+                message: "Enter a host name or IP address, without a port or http://.".to_string(),
+            });
+        }
+
+        if let Some(path) = &self.ableton.remote_scripts_path {
+            if !path.is_absolute() {
+                errors.push(FieldError {
+                    field: "ableton.remoteScriptsPath".into(),
+                    message: "Enter a full path.".to_string(),
+                });
+            } else if path.is_file() {
+                errors.push(FieldError {
+                    field: "ableton.remoteScriptsPath".into(),
+                    message: "This is a file, not a folder.".to_string(),
+                });
+            }
+        }
+
+        errors.extend(self.midi.validate());
+        errors.extend(self.relay.validate());
+        errors.extend(self.osc_control.validate());
+        errors
+    }
+
+    /// Whether switching from `self` to `other` needs the bridge restarted
+    pub fn needs_bridge_restart(&self, other: &Settings) -> bool {
+        self.bridge != other.bridge || osc_proxy::needed(self) != osc_proxy::needed(other)
+    }
+}
+
+/// The settings file at `path`, if a newer version of the app wrote it
+fn newer_file(path: &Path) -> Option<Value> {
+    let value: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    let version = value.get("version").and_then(Value::as_u64)?;
+    (version > u64::from(SETTINGS_VERSION)).then_some(value)
+}
+
+/// Write `from` over `into`, keeping keys of `into`'s objects that `from`
+/// doesn't have
+fn merge(into: &mut Value, from: Value) {
+    match (into, from) {
+        (Value::Object(into), Value::Object(from)) => {
+            for (key, value) in from {
+                match into.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        into.insert(key, value);
+                    }
+                }
+            }
+        }
+        (into, from) => *into = from,
+    }
+}
+
+/// Upgrade a settings object from version `from` to `from + 1`
+fn migrate(object: &mut Map<String, Value>, from: u32) {
+    // Version 1 kept the update settings at the top level
+    if from == 1 {
+        let mut updates = Map::new();
+        for (old, new) in [
+            ("updateChannel", "channel"),
+            ("skippedVersions", "skippedVersions"),
+            ("snoozedUntil", "snoozedUntil"),
+        ] {
+            if let Some(value) = object.remove(old) {
+                updates.insert(new.to_string(), value);
+            }
+        }
+        object.insert("updates".to_string(), Value::Object(updates));
+    }
+    object.insert("version".to_string(), Value::from(from + 1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn fields(settings: &Settings) -> Vec<String> {
+        settings.validate().into_iter().map(|error| error.field.into_owned()).collect()
+    }
+
+    #[test]
+    fn migrates_version_1_update_settings() {
+        let settings = Settings::from_json(json!({
+            "updateChannel": "edge",
+            "skippedVersions": ["0.0.6"],
+            "snoozedUntil": 1760000000,
+            "bridge": { "uiPort": 6000 },
+        }))
+        .unwrap();
+
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.updates.channel, UpdateChannel::Edge);
+        assert_eq!(settings.updates.skipped_versions, vec!["0.0.6".to_string()]);
+        assert_eq!(settings.updates.snoozed_until, Some(1760000000));
+        assert!(settings.updates.automatic);
+        assert_eq!(settings.bridge.ui_port, 6000);
+    }
+
+    #[test]
+    fn migrates_one_version_at_a_time() {
+        let mut object = json!({ "version": 1, "updateChannel": "stable" }).as_object().unwrap().clone();
+        migrate(&mut object, 1);
+        assert_eq!(Value::Object(object), json!({ "version": 2, "updates": { "channel": "stable" } }));
+    }
+
+    #[test]
+    fn keeps_what_a_newer_version_wrote() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        let newer = json!({
+            "version": SETTINGS_VERSION + 1,
+            "bridge": { "uiPort": 6000, "tunnel": true },
+            "futureFeature": { "enabled": true },
+        });
+        fs::write(&path, newer.to_string()).unwrap();
+
+        let mut settings = Settings::load(&path);
+        assert_eq!(settings.bridge.ui_port, 6000);
+        settings.bridge.ui_port = 7000;
+        settings.save(&path).unwrap();
+
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], SETTINGS_VERSION + 1);
+        assert_eq!(saved["bridge"]["uiPort"], 7000);
+        assert_eq!(saved["bridge"]["tunnel"], true);
+        assert_eq!(saved["futureFeature"], json!({ "enabled": true }));
+    }
+
+    #[test]
+    fn saves_older_files_in_the_current_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETTINGS_FILE);
+        fs::write(&path, json!({ "updateChannel": "edge" }).to_string()).unwrap();
+
+        Settings::load(&path).save(&path).unwrap();
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["version"], SETTINGS_VERSION);
+        assert_eq!(saved["updates"]["channel"], "edge");
+        assert!(saved.get("updateChannel").is_none());
+    }
+
+    #[test]
+    fn rejects_files_that_are_not_objects() {
+        assert!(Settings::from_json(json!([1, 2])).is_err());
+        assert!(Settings::from_json(json!({ "bridge": { "uiPort": "high" } })).is_err());
+    }
+
+    #[test]
+    fn accepts_the_defaults() {
+        assert_eq!(fields(&Settings::default()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn checks_the_ui_port() {
+        for port in [80, osc::OSC_SEND_PORT, osc::OSC_RECEIVE_PORT] {
+            let mut settings = Settings::default();
+            settings.bridge.ui_port = port;
+            assert_eq!(fields(&settings), ["bridge.uiPort"], "port {}", port);
+        }
+    }
+
+    #[test]
+    fn checks_the_sidecar_name() {
+        for sidecar in ["", "../bin/bridge", "C:\\bridge.exe"] {
+            let mut settings = Settings::default();
+            settings.bridge.sidecar = sidecar.to_string();
+            assert_eq!(fields(&settings), ["bridge.sidecar"], "sidecar {:?}", sidecar);
+        }
+    }
+
+    #[test]
+    fn checks_the_osc_host() {
+        for host in ["", "http://studio.local", "studio.local:11000/"] {
+            let mut settings = Settings::default();
+            settings.bridge.osc_host = host.to_string();
+            assert_eq!(fields(&settings), ["bridge.oscHost"], "host {:?}", host);
+        }
+        let mut settings = Settings::default();
+        settings.bridge.osc_host = "192.168.1.20".to_string();
+        assert_eq!(fields(&settings), Vec::<String>::new());
+    }
+
+    #[test]
+    fn checks_the_remote_scripts_path() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        for path in [PathBuf::from("Remote Scripts"), file.path().to_path_buf()] {
+            let mut settings = Settings::default();
+            settings.ableton.remote_scripts_path = Some(path.clone());
+            assert_eq!(fields(&settings), ["ableton.remoteScriptsPath"], "path {:?}", path);
+        }
+    }
+
+    #[test]
+    fn checks_the_shortcuts() {
+        let shortcut = |keys: &str, action| Shortcut { keys: keys.to_string(), action };
+        let errors = crate::hotkeys::validate(&[
+            shortcut("CmdOrCtrl+Shift+P", ShortcutAction::PlayStop),
+            shortcut("Ctrl+Banana", ShortcutAction::TapTempo),
+            shortcut(" ", ShortcutAction::StopAll),
+            shortcut("Shift+CmdOrCtrl+P", ShortcutAction::NextScene),
+            shortcut("Alt+1", ShortcutAction::FireScene { scene: 0 }),
+            shortcut("Alt+2", ShortcutAction::FireScene { scene: 2 }),
+        ]);
+        let errors: Vec<(&str, &str)> = errors.iter().map(|e| (e.field.as_ref(), e.message.as_str())).collect();
+
+        assert_eq!(errors.len(), 4, "{:?}", errors);
+        assert_eq!(errors[0].0, "shortcuts.1.keys");
+        assert!(errors[0].1.starts_with("Not a shortcut: "), "{:?}", errors[0]);
+        assert_eq!(errors[1], ("shortcuts.2.keys", "Press the keys for this shortcut."));
+        // The same keys in another order
+        assert_eq!(errors[2], ("shortcuts.3.keys", "Already used by shortcut 1."));
+        assert_eq!(errors[3], ("shortcuts.4.keys", "Scenes are numbered from 1."));
+    }
+
+    #[test]
+    fn includes_errors_from_each_section() {
+        let mut settings = Settings::default();
+        settings.osc_control.port = osc::OSC_RECEIVE_PORT;
+        settings.relay.targets = vec!["studio.local".to_string()];
+        assert_eq!(fields(&settings), ["relay.targets", "oscControl.port"]);
+    }
+}
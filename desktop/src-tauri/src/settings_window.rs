@@ -0,0 +1,138 @@
+//! Settings window for editing the settings file
+
+use tauri::{webview::WebviewWindowBuilder, AppHandle, Manager, WebviewWindow, WindowEvent};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::midi_mapping::Trigger;
+use crate::settings::{FieldError, Settings};
+
+const PAGE: &str = include_str!("../pages/settings.html");
+
+/// Buttons navigate to `mcsettings://<action>[?<json>]` instead of using IPC,
+/// since the page is loaded from a data URL
+const ACTION_SCHEME: &str = "mcsettings";
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Settings as edited on the page, not yet validated
+    Save(Result<Settings, String>),
+    /// Pick the Remote Scripts folder
+    Browse,
+    /// Wait for a MIDI note or CC for the mapping with this field name
+    Learn(String),
+    Close,
+    /// The user closed the window
+    Closed,
+}
+
+#[derive(Clone)]
+pub struct SettingsWindow {
+    window: WebviewWindow,
+}
+
+/// Open the Settings window. Returns `None` if it is already open, after
+/// focusing it.
+pub fn open(
+    app: &AppHandle,
+    settings: &Settings,
+    default_remote_scripts_path: Option<String>,
+) -> Result<Option<(SettingsWindow, UnboundedReceiver<Action>)>, String> {
+    if let Some(window) = app.get_webview_window("settings") {
+        let _ = window.set_focus();
+        return Ok(None);
+    }
+
+    let data = serde_json::json!({
+        "settings": settings,
+        "defaults": { "remoteScriptsPath": default_remote_scripts_path },
+        "monitors": crate::kiosk::monitor_names(app),
+        "midiInputs": crate::midi::input_ports(),
+        "midiOutputs": crate::midi::output_ports(),
+    });
+
+    let (sender, actions) = mpsc::unbounded_channel();
+    let navigation_sender = sender.clone();
+
+    let data_url = format!("data:text/html,{}", urlencoding::encode(PAGE))
+        .parse()
+        .map_err(|e| format!("Failed to parse data URL: {}", e))?;
+
+    let window = WebviewWindowBuilder::new(app, "settings", tauri::WebviewUrl::External(data_url))
+        .title("Mission Control Settings")
+        .inner_size(480.0, 640.0)
+        .resizable(false)
+        .center()
+        .initialization_script(format!("window.__SETTINGS__ = {};", data))
+        .on_navigation(move |url| {
+            if url.scheme() != ACTION_SCHEME {
+                return true;
+            }
+            let action = match url.host_str() {
+                Some("save") => Action::Save(parse_settings(url.query().unwrap_or_default())),
+                Some("browse") => Action::Browse,
+                Some("learn") => match parse_field(url.query().unwrap_or_default()) {
+                    Some(field) => Action::Learn(field),
+                    None => return false,
+                },
+                Some("close") => Action::Close,
+                _ => return false,
+            };
+            let _ = navigation_sender.send(action);
+            false
+        })
+        .build()
+        .map_err(|e| format!("Failed to create settings window: {}", e))?;
+
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            let _ = sender.send(Action::Closed);
+        }
+    });
+
+    Ok(Some((SettingsWindow { window }, actions)))
+}
+
+fn parse_settings(query: &str) -> Result<Settings, String> {
+    let json = urlencoding::decode(query).map_err(|e| format!("Invalid settings: {}", e))?;
+    let value = serde_json::from_str(&json).map_err(|e| format!("Invalid settings: {}", e))?;
+    Settings::from_json(value)
+}
+
+/// A field name sent as a JSON string
+fn parse_field(query: &str) -> Option<String> {
+    let json = urlencoding::decode(query).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+impl SettingsWindow {
+    pub fn show_errors(&self, errors: &[FieldError]) {
+        let errors = serde_json::to_string(errors).unwrap_or_else(|_| "[]".to_string());
+        let _ = self.window.eval(format!("window.showErrors({})", errors));
+    }
+
+    /// Show a general error not tied to a field
+    pub fn show_error(&self, message: &str) {
+        self.show_errors(&[FieldError { field: "".into(), message: message.to_string() }]);
+    }
+
+    pub fn saved(&self, message: &str) {
+        let message = serde_json::to_string(message).unwrap_or_default();
+        let _ = self.window.eval(format!("window.saved({})", message));
+    }
+
+    pub fn close(&self) {
+        let _ = self.window.destroy();
+    }
+
+    /// Show the trigger MIDI learn heard for `field`, or that it heard nothing
+    pub fn set_learned(&self, field: &str, trigger: Option<Trigger>) {
+        let field = serde_json::to_string(field).unwrap_or_default();
+        let trigger = serde_json::to_string(&trigger).unwrap_or_default();
+        let _ = self.window.eval(format!("window.setLearned({}, {})", field, trigger));
+    }
+
+    pub fn set_remote_scripts_path(&self, path: &str) {
+        let path = serde_json::to_string(path).unwrap_or_default();
+        let _ = self.window.eval(format!("window.setRemoteScriptsPath({})", path));
+    }
+}
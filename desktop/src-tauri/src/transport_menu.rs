@@ -0,0 +1,138 @@
+//! Transport controls in the tray menu, reflecting Live's state
+
+use std::sync::Mutex;
+
+use mission_control_protocol::{ClientMessage, Snapshot};
+use tauri::menu::{CheckMenuItem, IsMenuItem, MenuItem};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::bridge_client;
+
+pub struct TransportMenu {
+    play: MenuItem<Wry>,
+    record: CheckMenuItem<Wry>,
+    metronome: CheckMenuItem<Wry>,
+    loop_: CheckMenuItem<Wry>,
+    tempo: MenuItem<Wry>,
+    shown: Mutex<Option<View>>,
+}
+
+/// What the items show, so they're only touched when it changes (the session
+/// changes every beat)
+#[derive(Debug, Clone, PartialEq)]
+pub struct View {
+    /// Connected with a session from Live
+    available: bool,
+    playing: bool,
+    recording: bool,
+    metronome: bool,
+    loop_: bool,
+    /// e.g. "120.00"
+    tempo: String,
+}
+
+impl View {
+    pub fn of(snapshot: &Snapshot) -> View {
+        match snapshot.live_session() {
+            Some(session) => View {
+                available: true,
+                playing: session.is_playing,
+                recording: session.is_recording,
+                metronome: session.metronome,
+                loop_: session.r#loop,
+                tempo: format!("{:.2}", session.tempo),
+            },
+            None => View {
+                available: false,
+                playing: false,
+                recording: false,
+                metronome: false,
+                loop_: false,
+                tempo: String::new(),
+            },
+        }
+    }
+}
+
+impl TransportMenu {
+    pub fn new(app: &AppHandle) -> tauri::Result<TransportMenu> {
+        Ok(TransportMenu {
+            play: MenuItem::with_id(app, "transport_play", "Play", false, None::<&str>)?,
+            record: CheckMenuItem::with_id(app, "transport_record", "Record", false, false, None::<&str>)?,
+            metronome: CheckMenuItem::with_id(app, "transport_metronome", "Metronome", false, false, None::<&str>)?,
+            loop_: CheckMenuItem::with_id(app, "transport_loop", "Loop", false, false, None::<&str>)?,
+            tempo: MenuItem::with_id(app, "transport_tempo", tempo_text(""), false, None::<&str>)?,
+            shown: Mutex::new(None),
+        })
+    }
+
+    /// The items, in menu order
+    pub fn items(&self) -> [&dyn IsMenuItem<Wry>; 5] {
+        [&self.play, &self.record, &self.metronome, &self.loop_, &self.tempo]
+    }
+}
+
+fn tempo_text(tempo: &str) -> String {
+    if tempo.is_empty() {
+        "Tempo: --".to_string()
+    } else {
+        format!("Tempo: {} BPM", tempo)
+    }
+}
+
+/// Show `view` in the tray, if it differs from what's shown
+pub fn update(app: &AppHandle, view: View) {
+    let Some(menu) = app.try_state::<TransportMenu>() else {
+        return;
+    };
+    {
+        // Not held while updating, as menu changes wait for the main thread
+        let mut shown = menu.shown.lock().unwrap();
+        if shown.as_ref() == Some(&view) {
+            return;
+        }
+        *shown = Some(view.clone());
+    }
+
+    let result = (|| {
+        menu.play.set_text(if view.playing { "Stop" } else { "Play" })?;
+        menu.record.set_checked(view.recording)?;
+        menu.metronome.set_checked(view.metronome)?;
+        menu.loop_.set_checked(view.loop_)?;
+        menu.tempo.set_text(tempo_text(&view.tempo))?;
+        for item in [&menu.record, &menu.metronome, &menu.loop_] {
+            item.set_enabled(view.available)?;
+        }
+        menu.play.set_enabled(view.available)
+    })();
+    if let Err(e) = result {
+        log::error!("Failed to update tray menu: {}", e);
+    }
+}
+
+/// Handle a click on one of the items. Returns `false` if `id` isn't ours.
+pub fn handle_menu_event(app: &AppHandle, id: &str) -> bool {
+    let Some(view) = app.state::<TransportMenu>().shown.lock().unwrap().clone() else {
+        return id.starts_with("transport_");
+    };
+
+    let message = match id {
+        "transport_play" if view.playing => ClientMessage::TransportStop,
+        "transport_play" => ClientMessage::TransportPlay,
+        "transport_record" => ClientMessage::TransportRecord { enabled: !view.recording },
+        "transport_metronome" => ClientMessage::TransportMetronome { enabled: !view.metronome },
+        "transport_loop" => ClientMessage::TransportLoop { enabled: !view.loop_ },
+        "transport_tempo" => return true,
+        _ => return false,
+    };
+    if let Err(e) = bridge_client::send(app, message) {
+        log::error!("[tray] {}", e);
+    }
+
+    // Clicking toggled the check mark; put it back until Live confirms
+    let menu = app.state::<TransportMenu>();
+    let _ = menu.record.set_checked(view.recording);
+    let _ = menu.metronome.set_checked(view.metronome);
+    let _ = menu.loop_.set_checked(view.loop_);
+    true
+}
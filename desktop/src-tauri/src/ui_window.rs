@@ -0,0 +1,95 @@
+//! Mission Control in its own window, as an alternative to a browser tab
+//!
+//! The window-state plugin remembers this window's size, position (and so its
+//! monitor) and full screen state between launches.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use tauri::{
+    webview::{NewWindowResponse, WebviewWindowBuilder},
+    AppHandle, Manager, Url, WebviewUrl, WebviewWindow, WindowEvent,
+};
+
+/// Label of the window, and its key in the window-state file
+pub const LABEL: &str = "ui";
+
+/// Open the window on `url`, or bring it to the front if it's already open.
+/// `on_fullscreen` is called whenever the window enters or leaves full screen.
+pub fn open(
+    app: &AppHandle,
+    url: &str,
+    always_on_top: bool,
+    on_fullscreen: impl Fn(bool) + Send + 'static,
+) -> Result<WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(LABEL) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+        return Ok(window);
+    }
+
+    let url: Url = url.parse().map_err(|e| format!("Invalid UI URL: {}", e))?;
+    let origin = url.origin();
+
+    let window = WebviewWindowBuilder::new(app, LABEL, WebviewUrl::External(url))
+        .title("Mission Control")
+        .inner_size(1280.0, 800.0)
+        .min_inner_size(640.0, 400.0)
+        .center()
+        .always_on_top(always_on_top)
+        // Links out of the UI (help, donations) open in the browser instead
+        .on_navigation(move |url| {
+            if url.origin() == origin {
+                return true;
+            }
+            open_in_browser(url);
+            false
+        })
+        .on_new_window(|url, _| {
+            open_in_browser(&url);
+            NewWindowResponse::Deny
+        })
+        .build()
+        .map_err(|e| format!("Failed to create Mission Control window: {}", e))?;
+
+    // An accessory app's windows don't get a Dock icon, which makes this one
+    // easy to lose, so be a regular app while it's open
+    #[cfg(target_os = "macos")]
+    let _ = app.set_activation_policy(tauri::ActivationPolicy::Regular);
+
+    let handle = window.clone();
+    #[cfg(target_os = "macos")]
+    let app = app.clone();
+    let fullscreen = AtomicBool::new(false);
+    window.on_window_event(move |event| match event {
+        WindowEvent::Resized(_) => {
+            let is_fullscreen = handle.is_fullscreen().unwrap_or(false);
+            if fullscreen.swap(is_fullscreen, Ordering::SeqCst) != is_fullscreen {
+                on_fullscreen(is_fullscreen);
+            }
+        }
+        WindowEvent::Destroyed => {
+            #[cfg(target_os = "macos")]
+            let _ = app.set_activation_policy(tauri::ActivationPolicy::Accessory);
+            on_fullscreen(false);
+        }
+        _ => {}
+    });
+
+    Ok(window)
+}
+
+/// Toggle full screen, returning the new state
+pub fn toggle_fullscreen(window: &WebviewWindow) -> Result<bool, String> {
+    let fullscreen = !window.is_fullscreen().unwrap_or(false);
+    window
+        .set_fullscreen(fullscreen)
+        .map_err(|e| format!("Could not change full screen: {}", e))?;
+    Ok(fullscreen)
+}
+
+fn open_in_browser(url: &Url) {
+    if let Err(e) = open::that(url.as_str()) {
+        log::error!("Failed to open browser: {}", e);
+    }
+}
@@ -0,0 +1,147 @@
+//! Update prompt with release notes, which turns into the download progress view
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tauri::{webview::WebviewWindowBuilder, AppHandle, Manager, WebviewWindow, WindowEvent};
+use tauri_plugin_updater::Update;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+use crate::updates;
+
+const PAGE: &str = include_str!("../pages/update.html");
+
+/// Buttons navigate to `mcupdate://<action>` instead of using IPC, since the page
+/// is loaded from a data URL
+const ACTION_SCHEME: &str = "mcupdate";
+
+/// How often to push progress to the page
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Install,
+    /// Skip this version
+    Skip,
+    /// Remind me tomorrow
+    Snooze,
+    Cancel,
+    /// The user closed the window
+    Closed,
+}
+
+pub struct UpdateWindow {
+    window: WebviewWindow,
+    /// (download started, last progress update)
+    progress_times: Mutex<Option<(Instant, Instant)>>,
+}
+
+/// Open the update window in its prompt view. Actions from the page arrive on
+/// the returned receiver.
+pub fn open(
+    app: &AppHandle,
+    update: &Update,
+    channel: updates::UpdateChannel,
+    downgrade: bool,
+) -> Result<(UpdateWindow, UnboundedReceiver<Action>), String> {
+    // A previous prompt may still be open from the startup check
+    if let Some(window) = app.get_webview_window("update") {
+        let _ = window.destroy();
+    }
+
+    let data = serde_json::json!({
+        "version": update.version,
+        "currentVersion": update.current_version,
+        "channel": channel.label().to_lowercase(),
+        "downgrade": downgrade,
+        "notesHtml": update.body.as_deref().map(updates::notes_html).unwrap_or_default(),
+    });
+
+    let (sender, actions) = mpsc::unbounded_channel();
+    let navigation_sender = sender.clone();
+
+    let data_url = format!("data:text/html,{}", urlencoding::encode(PAGE))
+        .parse()
+        .map_err(|e| format!("Failed to parse data URL: {}", e))?;
+
+    let window = WebviewWindowBuilder::new(app, "update", tauri::WebviewUrl::External(data_url))
+        .title("Mission Control Update")
+        .inner_size(480.0, 420.0)
+        .resizable(false)
+        .center()
+        .initialization_script(format!("window.__UPDATE__ = {};", data))
+        .on_navigation(move |url| {
+            // Links in the release notes open in the browser
+            if matches!(url.scheme(), "http" | "https") {
+                if let Err(e) = open::that(url.as_str()) {
+                    log::error!("Failed to open link: {}", e);
+                }
+                return false;
+            }
+            if url.scheme() != ACTION_SCHEME {
+                return true;
+            }
+            let action = match url.host_str() {
+                Some("install") => Action::Install,
+                Some("skip") => Action::Skip,
+                Some("snooze") => Action::Snooze,
+                Some("cancel") => Action::Cancel,
+                _ => return false,
+            };
+            let _ = navigation_sender.send(action);
+            false
+        })
+        .build()
+        .map_err(|e| format!("Failed to create update window: {}", e))?;
+
+    window.on_window_event(move |event| {
+        if let WindowEvent::Destroyed = event {
+            let _ = sender.send(Action::Closed);
+        }
+    });
+
+    Ok((
+        UpdateWindow { window, progress_times: Mutex::new(None) },
+        actions,
+    ))
+}
+
+impl UpdateWindow {
+    /// Report download progress, throttled to `PROGRESS_INTERVAL`
+    pub fn set_progress(&self, downloaded: u64, total: Option<u64>) {
+        let now = Instant::now();
+        let started = {
+            let mut times = self.progress_times.lock().unwrap();
+            match *times {
+                Some((_, last)) if now - last < PROGRESS_INTERVAL => return,
+                // Measure speed from the first chunk, not from when the prompt opened
+                Some((started, _)) => {
+                    *times = Some((started, now));
+                    started
+                }
+                None => {
+                    *times = Some((now, now));
+                    now
+                }
+            }
+        };
+
+        let elapsed = (now - started).as_secs_f64();
+        let speed = if elapsed > 0.0 { (downloaded as f64 / elapsed) as u64 } else { 0 };
+        let total = total.map_or("null".to_string(), |t| t.to_string());
+        let _ = self.window.eval(format!("window.setProgress({}, {}, {})", downloaded, total, speed));
+    }
+
+    /// The download is done but a show is in progress
+    pub fn set_waiting(&self) {
+        let _ = self.window.eval("window.setWaiting()");
+    }
+
+    pub fn set_installing(&self) {
+        let _ = self.window.eval("window.setInstalling()");
+    }
+
+    pub fn close(&self) {
+        let _ = self.window.destroy();
+    }
+}
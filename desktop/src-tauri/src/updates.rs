@@ -0,0 +1,287 @@
+//! Update channels and version comparison for the updater
+
+use std::net::IpAddr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use minisign_verify::{PublicKey, Signature};
+use pulldown_cmark::{html, Event, Parser};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::UpdateSettings;
+
+/// Manifest for tagged releases
+pub const STABLE_ENDPOINT: &str =
+    "https://github.com/ryangavin/mission-control/releases/latest/download/latest.json";
+/// Manifest for builds from the tip of main (see .github/workflows/edge.yml)
+pub const EDGE_ENDPOINT: &str =
+    "https://github.com/ryangavin/mission-control/releases/download/edge/latest.json";
+
+/// How often to check for updates in the background
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+/// How soon to check again when a show held back the last check
+pub const BUSY_RETRY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// How often to look again while a downloaded update waits for a show to end
+pub const BUSY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// How long "Remind Me Tomorrow" snoozes automatic prompts
+pub const SNOOZE_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Stable,
+    Edge,
+}
+
+impl Default for UpdateChannel {
+    /// Builds follow the channel they were published on
+    fn default() -> Self {
+        UpdateChannel::of_version(env!("CARGO_PKG_VERSION"))
+    }
+}
+
+impl UpdateChannel {
+    /// The channel a version was published on. Edge builds are versioned
+    /// `<next>-edge.<run>`, so they sort below the release they lead up to.
+    pub fn of_version(version: &str) -> UpdateChannel {
+        match Version::parse(version) {
+            Ok(version) if version.pre.as_str().starts_with("edge") => UpdateChannel::Edge,
+            Ok(_) => UpdateChannel::Stable,
+            Err(_) if version.contains("edge") => UpdateChannel::Edge,
+            Err(_) => UpdateChannel::Stable,
+        }
+    }
+
+    pub fn endpoint(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => STABLE_ENDPOINT,
+            UpdateChannel::Edge => EDGE_ENDPOINT,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "Stable",
+            UpdateChannel::Edge => "Edge",
+        }
+    }
+}
+
+/// Decide whether to offer `remote` from the manifest of `channel`.
+///
+/// Within a channel only newer versions are offered. After switching channels
+/// any different version is offered, since the latest stable release is usually
+/// older than the edge build being left (callers confirm downgrades).
+pub fn should_offer(channel: UpdateChannel, current: &Version, remote: &Version) -> bool {
+    if UpdateChannel::of_version(&current.to_string()) != channel {
+        remote != current
+    } else {
+        remote > current
+    }
+}
+
+/// Whether installing `remote` would move to an older version
+pub fn is_downgrade(current: &str, remote: &str) -> bool {
+    match (Version::parse(current), Version::parse(remote)) {
+        (Ok(current), Ok(remote)) => remote < current,
+        _ => false,
+    }
+}
+
+/// Whether automatic checks should stay quiet about `version`, because the user
+/// skipped it or snoozed update prompts
+pub fn is_suppressed(settings: &UpdateSettings, version: &str, now: SystemTime) -> bool {
+    settings.skipped_versions.iter().any(|v| v == version)
+        || settings.snoozed_until.is_some_and(|until| unix_secs(now) < until)
+}
+
+pub fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Refuse plain HTTP except on loopback. tauri.conf.json allows it for
+/// `offline_update`'s local server, so every endpoint goes through here.
+pub fn secure_endpoint(url: &tauri::Url) -> Result<(), String> {
+    let loopback = match url.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_matches(['[', ']'])
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    };
+    if url.scheme() == "https" || loopback {
+        Ok(())
+    } else {
+        Err(format!("Refusing to check for updates over {}: {}", url.scheme(), url))
+    }
+}
+
+/// Normalize the contents of a `.sig` file to the base64 form used in update
+/// manifests. Tauri writes signatures that way; raw minisign output is accepted too.
+pub fn signature_base64(contents: &str) -> String {
+    let contents = contents.trim();
+    if contents.starts_with("untrusted comment:") {
+        BASE64.encode(contents)
+    } else {
+        contents.to_string()
+    }
+}
+
+/// Check an update bundle against its signature and the updater's public key,
+/// the same way the updater checks downloads. Both are base64 as they appear in
+/// tauri.conf.json and `latest.json`.
+pub fn verify_bundle(bundle: &[u8], signature: &str, pubkey: &str) -> Result<(), String> {
+    let public_key = decode_base64_text(pubkey)
+        .and_then(|text| PublicKey::decode(&text).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Invalid updater public key: {}", e))?;
+    let signature = decode_base64_text(signature)
+        .and_then(|text| Signature::decode(&text).map_err(|e| e.to_string()))
+        .map_err(|e| format!("Invalid signature file: {}", e))?;
+
+    public_key
+        .verify(bundle, &signature, true)
+        .map_err(|_| "The signature doesn't match this update. It may be damaged or not from Mission Control.".to_string())
+}
+
+fn decode_base64_text(encoded: &str) -> Result<String, String> {
+    let bytes = BASE64.decode(encoded.trim()).map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// Version in a bundle's file name, e.g. `Mission Control Bridge_0.0.8_x64-setup.exe`.
+/// macOS bundles don't include one.
+pub fn version_from_file_name(name: &str) -> Option<Version> {
+    name.split('_').find_map(|part| Version::parse(part).ok())
+}
+
+/// Render release notes (GitHub-flavoured markdown) to HTML for the update
+/// window. Raw HTML in the notes is shown as text rather than rendered.
+pub fn notes_html(markdown: &str) -> String {
+    let events = Parser::new(markdown).map(|event| match event {
+        Event::Html(raw) | Event::InlineHtml(raw) => Event::Text(raw),
+        event => event,
+    });
+
+    let mut output = String::new();
+    html::push_html(&mut output, events);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUNDLE: &[u8] = include_bytes!("../tests/fixtures/updates/Mission Control Bridge_0.0.8_x64-setup.exe");
+    const SIGNATURE: &str = include_str!("../tests/fixtures/updates/Mission Control Bridge_0.0.8_x64-setup.exe.sig");
+    const PUBKEY: &str = include_str!("../tests/fixtures/updates/key.pub");
+    const OTHER_PUBKEY: &str = include_str!("../tests/fixtures/updates/other.pub");
+
+    fn version(text: &str) -> Version {
+        Version::parse(text).unwrap()
+    }
+
+    #[test]
+    fn tells_channels_from_versions() {
+        assert_eq!(UpdateChannel::of_version("0.0.8"), UpdateChannel::Stable);
+        assert_eq!(UpdateChannel::of_version("0.0.9-edge.41"), UpdateChannel::Edge);
+        assert_eq!(UpdateChannel::of_version("0.0.9-beta.1"), UpdateChannel::Stable);
+        assert_eq!(UpdateChannel::of_version("edge-build"), UpdateChannel::Edge);
+        assert_eq!(UpdateChannel::of_version("nonsense"), UpdateChannel::Stable);
+    }
+
+    #[test]
+    fn offers_only_newer_versions_within_a_channel() {
+        let stable = UpdateChannel::Stable;
+        assert!(should_offer(stable, &version("0.0.7"), &version("0.0.8")));
+        assert!(!should_offer(stable, &version("0.0.8"), &version("0.0.8")));
+        assert!(!should_offer(stable, &version("0.0.8"), &version("0.0.7")));
+
+        let edge = UpdateChannel::Edge;
+        assert!(should_offer(edge, &version("0.0.9-edge.41"), &version("0.0.9-edge.42")));
+        assert!(!should_offer(edge, &version("0.0.9-edge.42"), &version("0.0.9-edge.41")));
+        // Edge run numbers compare as numbers, not text
+        assert!(should_offer(edge, &version("0.0.9-edge.9"), &version("0.0.9-edge.10")));
+        assert!(should_offer(edge, &version("0.0.9-edge.42"), &version("0.0.10-edge.1")));
+    }
+
+    #[test]
+    fn offers_any_other_version_after_switching_channels() {
+        // Stable to edge: the edge build leads up to the next release
+        assert!(should_offer(UpdateChannel::Edge, &version("0.0.8"), &version("0.0.9-edge.1")));
+        // Edge to stable: the latest release is usually older
+        assert!(should_offer(UpdateChannel::Stable, &version("0.0.9-edge.42"), &version("0.0.8")));
+        assert!(should_offer(UpdateChannel::Stable, &version("0.0.9-edge.42"), &version("0.0.9")));
+        assert!(!should_offer(UpdateChannel::Edge, &version("0.0.8"), &version("0.0.8")));
+    }
+
+    #[test]
+    fn orders_pre_releases_before_their_release() {
+        assert!(is_downgrade("0.0.9-edge.42", "0.0.8"));
+        assert!(!is_downgrade("0.0.9-edge.42", "0.0.9"));
+        assert!(is_downgrade("0.0.9", "0.0.9-edge.42"));
+        assert!(is_downgrade("0.0.9-edge.10", "0.0.9-edge.9"));
+        assert!(!is_downgrade("0.0.8", "0.0.8"));
+        assert!(!is_downgrade("0.0.8", "not a version"));
+    }
+
+    #[test]
+    fn verifies_a_signed_bundle() {
+        assert_eq!(verify_bundle(BUNDLE, &signature_base64(SIGNATURE), PUBKEY), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_tampered_bundle() {
+        let mut tampered = BUNDLE.to_vec();
+        tampered[0] ^= 1;
+        let error = verify_bundle(&tampered, &signature_base64(SIGNATURE), PUBKEY).unwrap_err();
+        assert!(error.starts_with("The signature doesn't match"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_another_key() {
+        let error = verify_bundle(BUNDLE, &signature_base64(SIGNATURE), OTHER_PUBKEY).unwrap_err();
+        assert!(error.starts_with("The signature doesn't match"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_a_malformed_signature() {
+        for signature in ["", "not base64!", &BASE64.encode("untrusted comment: nothing else\n")] {
+            let error = verify_bundle(BUNDLE, &signature_base64(signature), PUBKEY).unwrap_err();
+            assert!(error.starts_with("Invalid signature file"), "{:?}: {}", signature, error);
+        }
+        let error = verify_bundle(BUNDLE, &signature_base64(SIGNATURE), "not a key").unwrap_err();
+        assert!(error.starts_with("Invalid updater public key"), "{}", error);
+    }
+
+    #[test]
+    fn accepts_raw_minisign_signatures() {
+        let raw = decode_base64_text(SIGNATURE).unwrap();
+        assert!(raw.starts_with("untrusted comment:"));
+        assert_eq!(signature_base64(&format!("\n{}\n", raw)), BASE64.encode(raw.trim()));
+        assert_eq!(signature_base64(&format!("{}\n", SIGNATURE.trim())), SIGNATURE.trim());
+        assert_eq!(verify_bundle(BUNDLE, &signature_base64(&raw), PUBKEY), Ok(()));
+    }
+
+    #[test]
+    fn reads_versions_from_file_names() {
+        let version = |name| version_from_file_name(name).map(|v| v.to_string());
+        assert_eq!(version("Mission Control Bridge_0.0.8_x64-setup.exe"), Some("0.0.8".into()));
+        assert_eq!(version("Mission Control Bridge_0.1.0-edge.42_amd64.AppImage"), Some("0.1.0-edge.42".into()));
+        assert_eq!(version("Mission Control Bridge.app.tar.gz"), None);
+        assert_eq!(version("Mission Control Bridge_latest_x64.msi"), None);
+    }
+
+    #[test]
+    fn allows_plain_http_only_on_loopback() {
+        let check = |url: &str| secure_endpoint(&url.parse().unwrap());
+        assert_eq!(check(STABLE_ENDPOINT), Ok(()));
+        assert_eq!(check(EDGE_ENDPOINT), Ok(()));
+        assert_eq!(check("http://127.0.0.1:50123/latest.json"), Ok(()));
+        assert_eq!(check("http://[::1]:50123/latest.json"), Ok(()));
+        assert_eq!(check("http://localhost/latest.json"), Ok(()));
+        assert!(check("http://github.com/latest.json").is_err());
+        assert!(check("http://192.168.1.20/latest.json").is_err());
+    }
+}
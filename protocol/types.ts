@@ -144,6 +144,15 @@ export type ServerMessage =
   | { type: 'sync_phase'; phase: string; progress?: number }
   | { type: 'error'; message: string };
 
+// Bridge status served at GET /api/status (used by the desktop app)
+export interface BridgeStatus {
+  version: string;        // Bridge version (package.json)
+  abletonConnected: boolean;
+  isPlaying: boolean;
+  clients: number;        // Connected WebSocket clients
+  remoteClients: number;  // Clients connected from other machines
+}
+
 // Connection Status
 
 export type ConnectionStatus = 'disconnected' | 'connecting' | 'connected' | 'error';
@@ -8,9 +8,10 @@ import type { IncomingMessage } from 'http';
 import type { Duplex } from 'stream';
 import OSC from 'osc-js';
 import type { Config } from './config';
-import type { OSCMessage, ClientMessage, ServerMessage, PatchPayload } from '../protocol';
+import type { OSCMessage, ClientMessage, ServerMessage, PatchPayload, BridgeStatus } from '../protocol';
 import { application } from '../protocol';
 import { SessionManager, SyncManager } from './state';
+import { version } from '../package.json';
 
 export interface BridgeOptions {
   config: Config;
@@ -21,6 +22,7 @@ export class Bridge {
   private wss: WebSocketServer;
   private osc: OSC | null = null;
   private clients: Set<WebSocket> = new Set();
+  private remoteClients: Set<WebSocket> = new Set();  // Clients from other machines
   private config: Config;
   private log: (message: string) => void;
   private abletonConnected = false;
@@ -77,8 +79,11 @@ export class Bridge {
    * Set up WebSocket connection handlers
    */
   private setupWebSocketHandlers(): void {
-    this.wss.on('connection', (ws: WebSocket) => {
+    this.wss.on('connection', (ws: WebSocket, request: IncomingMessage) => {
       this.handleClientConnection(ws);
+      if (!isLoopbackAddress(request.socket.remoteAddress)) {
+        this.remoteClients.add(ws);
+      }
 
       ws.on('message', (data) => {
         this.handleClientMessage(ws, data.toString());
@@ -87,11 +92,13 @@ export class Bridge {
       ws.on('close', () => {
         this.log('Client disconnected');
         this.clients.delete(ws);
+        this.remoteClients.delete(ws);
       });
 
       ws.on('error', (error) => {
         this.log(`Client error: ${error.message}`);
         this.clients.delete(ws);
+        this.remoteClients.delete(ws);
       });
     });
   }
@@ -108,11 +115,13 @@ export class Bridge {
         this.osc = new OSC({
           plugin: new DatagramPlugin({
             open: {
-              host: 'localhost',
+              // AbletonOSC replies to the sender's host, so listen on all
+              // interfaces when Live is on another machine
+              host: isLoopbackAddress(this.config.oscHost) ? 'localhost' : '0.0.0.0',
               port: this.config.oscReceivePort,
             },
             send: {
-              host: 'localhost',
+              host: this.config.oscHost,
               port: this.config.oscSendPort,
             },
           }),
@@ -184,6 +193,7 @@ export class Bridge {
       client.close();
     }
     this.clients.clear();
+    this.remoteClients.clear();
 
     // Close WebSocket server
     this.wss.close();
@@ -197,6 +207,19 @@ export class Bridge {
     this.log('Bridge stopped');
   }
 
+  /**
+   * Get a status summary for the desktop app (served at GET /api/status)
+   */
+  getStatus(): BridgeStatus {
+    return {
+      version,
+      abletonConnected: this.abletonConnected,
+      isPlaying: this.abletonConnected && this.session.getState().isPlaying,
+      clients: this.clients.size,
+      remoteClients: this.remoteClients.size,
+    };
+  }
+
   /**
    * Handle a new WebSocket client connection
    */
@@ -898,3 +921,11 @@ export class Bridge {
     }
   }
 }
+
+/**
+ * Check whether a socket address is on this machine
+ */
+function isLoopbackAddress(address: string | undefined): boolean {
+  if (!address) return true;
+  return address === '::1' || address === 'localhost' || address.startsWith('127.') || address.startsWith('::ffff:127.');
+}
@@ -1,7 +1,10 @@
-import { homedir, platform } from 'os';
+import { homedir, platform, userInfo } from 'os';
 import { join } from 'path';
 
 export interface Config {
+  // Host running Ableton Live with AbletonOSC (default: localhost)
+  oscHost: string;
+
   // OSC ports for Ableton communication
   oscSendPort: number;     // Port to send OSC messages to Ableton (default: 11000)
   oscReceivePort: number;  // Port to receive OSC messages from Ableton (default: 11001)
@@ -11,6 +14,7 @@ export interface Config {
 }
 
 export const defaultConfig: Config = {
+  oscHost: 'localhost',
   oscSendPort: 11000,
   oscReceivePort: 11001,
   remoteScriptName: 'AbletonOSC',
@@ -29,6 +33,12 @@ export function getRemoteScriptsPath(): string {
     case 'win32':
       // Windows
       return join(homedir(), 'Documents', 'Ableton', 'User Library', 'Remote Scripts');
+    case 'linux': {
+      // Live runs under Wine, so its User Library is in the Wine prefix
+      const prefix = process.env.WINEPREFIX ?? join(homedir(), '.wine');
+      const user = process.env.USER ?? userInfo().username;
+      return join(prefix, 'drive_c', 'users', user, 'Documents', 'Ableton', 'User Library', 'Remote Scripts');
+    }
     default:
       throw new Error(`Unsupported operating system: ${os}`);
   }
@@ -11,9 +11,54 @@ import { defaultConfig } from './config';
 import { join, resolve, extname } from 'path';
 import { statSync, readFileSync, existsSync } from 'fs';
 
-const PORT = 5555;
+const DEFAULT_PORT = 5555;
 const HOST = '0.0.0.0';
 
+// Get a numeric `--name <value>` command line argument
+function getNumberArg(name: string): number | undefined {
+  const index = process.argv.indexOf(`--${name}`);
+  if (index === -1) return undefined;
+
+  const value = Number(process.argv[index + 1]);
+  if (!Number.isInteger(value) || value <= 0 || value > 65535) {
+    console.error(`[standalone] Invalid --${name}: ${process.argv[index + 1]}`);
+    process.exit(1);
+  }
+  return value;
+}
+
+// Get a `--name <value>` command line argument
+function getStringArg(name: string): string | undefined {
+  const index = process.argv.indexOf(`--${name}`);
+  if (index === -1) return undefined;
+
+  const value = process.argv[index + 1];
+  if (!value || value.startsWith('--')) {
+    console.error(`[standalone] Missing value for --${name}`);
+    process.exit(1);
+  }
+  return value;
+}
+
+const LOG_LEVELS = ['off', 'error', 'warn', 'info', 'debug', 'trace'];
+
+// The desktop app passes these from its settings and command line
+const PORT = getNumberArg('port') ?? DEFAULT_PORT;
+const OSC_HOST = getStringArg('osc-host') ?? defaultConfig.oscHost;
+// The app's OSC proxy takes AbletonOSC's reply port and passes replies on here
+const OSC_RECEIVE_PORT = getNumberArg('osc-receive-port') ?? defaultConfig.oscReceivePort;
+// While capturing, the proxy also takes what we send and passes it on to AbletonOSC
+const OSC_SEND_PORT = getNumberArg('osc-send-port') ?? defaultConfig.oscSendPort;
+const LOG_LEVEL = getStringArg('log-level') ?? 'info';
+
+if (!LOG_LEVELS.includes(LOG_LEVEL)) {
+  console.error(`[standalone] Invalid --log-level: ${LOG_LEVEL}`);
+  process.exit(1);
+}
+
+// Bridge messages are informational, so only print them at info and above
+const logBridge = LOG_LEVELS.indexOf(LOG_LEVEL) >= LOG_LEVELS.indexOf('info');
+
 // Get local network IP addresses
 function getNetworkAddresses(): string[] {
   const interfaces = networkInterfaces();
@@ -84,8 +129,15 @@ console.log(`[standalone] Serving static files from: ${distPath}`);
 
 // Create bridge instance
 const bridge = new Bridge({
-  config: defaultConfig,
-  onLog: (msg) => console.log(`[bridge] ${msg}`),
+  config: {
+    ...defaultConfig,
+    oscHost: OSC_HOST,
+    oscSendPort: OSC_SEND_PORT,
+    oscReceivePort: OSC_RECEIVE_PORT,
+  },
+  onLog: (msg) => {
+    if (logBridge) console.log(`[bridge] ${msg}`);
+  },
 });
 
 // Create HTTP server
@@ -93,6 +145,13 @@ const server = createServer((req, res) => {
   const url = new URL(req.url!, `http://${req.headers.host}`);
   let filePath = url.pathname;
 
+  // Status for the desktop app
+  if (filePath === '/api/status') {
+    res.writeHead(200, { 'Content-Type': 'application/json' });
+    res.end(JSON.stringify(bridge.getStatus()));
+    return;
+  }
+
   // Default to index.html
   if (filePath === '/') {
     filePath = '/index.html';
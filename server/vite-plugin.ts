@@ -35,6 +35,12 @@ export function bridgePlugin(): Plugin {
         // Let Vite handle its own HMR WebSocket upgrades (usually on /)
       });
 
+      // Status for the desktop app
+      server.middlewares.use('/api/status', (_req, res) => {
+        res.setHeader('Content-Type', 'application/json');
+        res.end(JSON.stringify(bridge!.getStatus()));
+      });
+
       // Cleanup on server close
       server.httpServer?.on('close', () => {
         bridge?.stop();
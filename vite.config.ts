@@ -11,4 +11,13 @@ export default defineConfig({
     port: 5173,
     allowedHosts: true,
   },
+  build: {
+    rollupOptions: {
+      input: {
+        main: 'index.html',
+        // Connect Another Device window in the desktop app
+        connect: 'connect.html',
+      },
+    },
+  },
 })